@@ -0,0 +1,33 @@
+mod parsers;
+
+pub use self::parsers::parse_albedo;
+
+// typical albedo of grass & soil, used for classes without own value
+pub const DEFAULT_ALBEDO: f64 = 0.2;
+
+#[derive(Debug, Clone)]
+pub enum Albedo {
+    /// single value is stored as 12 equal monthly values
+    Monthly([f64; 12]),
+    /// albedo of the LAS classification codes
+    Classes {
+        albedos: Vec<(u8, f64)>,
+        default: f64,
+    },
+}
+
+impl Albedo {
+    pub fn get_val(&self, month: u32, classification: Option<u8>) -> f64 {
+        match self {
+            Albedo::Monthly(albedos) => albedos[month as usize - 1],
+            Albedo::Classes { albedos, default } => classification
+                .and_then(|classification| {
+                    albedos
+                        .iter()
+                        .find(|(class, _)| *class == classification)
+                        .map(|(_, albedo)| *albedo)
+                })
+                .unwrap_or(*default),
+        }
+    }
+}
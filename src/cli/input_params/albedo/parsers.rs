@@ -0,0 +1,92 @@
+use super::{Albedo, DEFAULT_ALBEDO};
+
+const INVALID_ALBEDO: &str = "Invalid albedo [Use single value, 12 (monthly) values or <CLASS>:<ALBEDO> pairs separated by comma, values from 0 to 1]";
+
+pub fn parse_albedo(input: &str) -> Result<Albedo, String> {
+    let parse_value = |value: &str| match value.trim().parse::<f64>() {
+        Ok(albedo) if (0. ..=1.).contains(&albedo) => Ok(albedo),
+        _ => Err(INVALID_ALBEDO.to_string()),
+    };
+
+    if input.contains(':') {
+        let mut albedos = vec![];
+        let mut default = None;
+        for item in input.split(',') {
+            match item.split_once(':') {
+                Some((class, albedo)) => {
+                    let class = class
+                        .trim()
+                        .parse::<u8>()
+                        .map_err(|_| INVALID_ALBEDO.to_string())?;
+                    albedos.push((class, parse_value(albedo)?));
+                }
+                // value without class is used for the other classes
+                None if default.is_none() => default = Some(parse_value(item)?),
+                None => return Err(INVALID_ALBEDO.to_string()),
+            }
+        }
+        return Ok(Albedo::Classes {
+            albedos,
+            default: default.unwrap_or(DEFAULT_ALBEDO),
+        });
+    }
+
+    let values = input
+        .split(',')
+        .map(parse_value)
+        .collect::<Result<Vec<f64>, String>>()?;
+    match values.len() {
+        1 => Ok(Albedo::Monthly([values[0]; 12])),
+        12 => Ok(Albedo::Monthly(values.try_into().unwrap())),
+        _ => Err(INVALID_ALBEDO.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_value() {
+        let albedo = parse_albedo("0.3").unwrap();
+        assert_eq!(albedo.get_val(1, None), 0.3);
+        assert_eq!(albedo.get_val(12, Some(2)), 0.3);
+    }
+
+    #[test]
+    fn parses_monthly_values() {
+        let albedo = parse_albedo("0.6,0.6,0.4,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.4, 0.6").unwrap();
+        assert_eq!(albedo.get_val(1, None), 0.6);
+        assert_eq!(albedo.get_val(4, None), 0.2);
+        assert_eq!(albedo.get_val(12, None), 0.6);
+    }
+
+    #[test]
+    fn parses_class_values() {
+        let albedo = parse_albedo("0.15, 2:0.25,9:0.06").unwrap();
+        assert_eq!(albedo.get_val(6, Some(2)), 0.25);
+        assert_eq!(albedo.get_val(6, Some(9)), 0.06);
+        assert_eq!(albedo.get_val(6, Some(6)), 0.15);
+        assert_eq!(albedo.get_val(6, None), 0.15);
+
+        let albedo = parse_albedo("2:0.25").unwrap();
+        assert_eq!(albedo.get_val(6, Some(6)), DEFAULT_ALBEDO);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for input in [
+            "",
+            "1.2",
+            "-0.1",
+            "0.2,0.3",
+            "x",
+            "2:1.5",
+            "256:0.2",
+            "a:0.2",
+            "0.1,0.2,2:0.3",
+        ] {
+            assert!(parse_albedo(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::common::Extent;
+
 mod parsers;
 
 pub use self::parsers::parse_block_params;
@@ -6,6 +8,16 @@ pub use self::parsers::parse_block_params;
 pub struct BlockParams {
     pub size: usize,
     pub overlap: usize,
+    pub shadow_halo: Option<BlockHalo>,
+}
+
+/// Extension of the block on each side (meters)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockHalo {
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+    pub top: f64,
 }
 
 impl Default for BlockParams {
@@ -13,6 +25,28 @@ impl Default for BlockParams {
         BlockParams {
             size: usize::MAX,
             overlap: 0,
+            shadow_halo: None,
+        }
+    }
+}
+
+impl BlockParams {
+    pub fn get_block_counts(&self, extent: &Extent<f64>) -> (usize, usize) {
+        let (x_length, y_length, _) = extent.get_dimensions();
+        let x_blocks = (x_length / self.size as f64).ceil() as usize;
+        let y_blocks = (y_length / self.size as f64).ceil() as usize;
+        (x_blocks, y_blocks)
+    }
+
+    /// Overlap extended by the shadow halo on each side
+    pub fn get_halo(&self) -> BlockHalo {
+        let overlap = self.overlap as f64;
+        let shadow_halo = self.shadow_halo.unwrap_or_default();
+        BlockHalo {
+            left: shadow_halo.left.max(overlap),
+            bottom: shadow_halo.bottom.max(overlap),
+            right: shadow_halo.right.max(overlap),
+            top: shadow_halo.top.max(overlap),
         }
     }
 }
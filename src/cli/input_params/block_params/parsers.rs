@@ -7,7 +7,11 @@ pub fn parse_block_params(input: &str) -> Result<BlockParams, String> {
 
     if let Ok(size) = size {
         if let Ok(overlap) = overlap {
-            Ok(BlockParams { size, overlap })
+            Ok(BlockParams {
+                size,
+                overlap,
+                shadow_halo: None,
+            })
         } else {
             Err("Invalid block overlap".to_string())
         }
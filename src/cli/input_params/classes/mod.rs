@@ -0,0 +1,14 @@
+mod parsers;
+
+pub use self::parsers::parse_classes;
+
+/// LAS classification codes
+#[derive(Debug, Clone)]
+pub struct Classes(pub Vec<u8>);
+
+impl Classes {
+    // points without classification (e.g. ply input) don't belong to any class
+    pub fn contains(&self, classification: Option<u8>) -> bool {
+        classification.is_some_and(|classification| self.0.contains(&classification))
+    }
+}
@@ -0,0 +1,10 @@
+use super::Classes;
+
+pub fn parse_classes(input: &str) -> Result<Classes, String> {
+    input
+        .split(',')
+        .map(|class| class.trim().parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map(Classes)
+        .map_err(|_| "Invalid classes [Use comma separated LAS classification codes]".to_string())
+}
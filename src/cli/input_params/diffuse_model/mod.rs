@@ -0,0 +1,15 @@
+mod parsers;
+
+pub use self::parsers::parse_diffuse_model;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffuseModel {
+    /// ESRA clear-sky model with the Muneer tilted surface function
+    Esra,
+    /// uniform sky radiance
+    Isotropic,
+    /// isotropic sky with the circumsolar region
+    HayDavies,
+    /// Perez 1990 - circumsolar region & horizon brightening
+    Perez,
+}
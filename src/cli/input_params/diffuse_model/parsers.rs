@@ -0,0 +1,11 @@
+use super::DiffuseModel;
+
+pub fn parse_diffuse_model(input: &str) -> Result<DiffuseModel, String> {
+    match input {
+        "esra" => Ok(DiffuseModel::Esra),
+        "isotropic" => Ok(DiffuseModel::Isotropic),
+        "hay-davies" => Ok(DiffuseModel::HayDavies),
+        "perez" => Ok(DiffuseModel::Perez),
+        _ => Err("Invalid diffuse model [Use esra, isotropic, hay-davies or perez]".to_string()),
+    }
+}
@@ -6,11 +6,16 @@ use std::fmt::Display;
 pub enum ParseFileError {
     NoFileType(String),
     UnsupportedFileType(String),
+    InvalidPath(String),
 }
 
 impl Display for ParseFileError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            ParseFileError::NoFileType(msg)
+            | ParseFileError::UnsupportedFileType(msg)
+            | ParseFileError::InvalidPath(msg) => write!(f, "{}", msg),
+        }
     }
 }
 
@@ -2,7 +2,7 @@ mod errors;
 mod parsers;
 
 pub use self::errors::ParseFileError;
-pub use self::parsers::parse_file;
+pub use self::parsers::{parse_file, parse_raster_file};
 
 #[derive(Debug, Clone)]
 pub enum FileType {
@@ -16,3 +16,15 @@ pub struct File {
     pub path: String,
     pub file_type: FileType,
 }
+
+#[derive(Debug, Clone)]
+pub enum RasterFileType {
+    GeoTiff,
+    EsriAscii,
+}
+
+#[derive(Debug)]
+pub struct RasterFile {
+    pub path: String,
+    pub file_type: RasterFileType,
+}
@@ -1,9 +1,9 @@
 use std::{ffi::OsStr, path::Path};
 
-use super::{File, FileType, ParseFileError};
+use super::{File, FileType, ParseFileError, RasterFile, RasterFileType};
 
 pub fn parse_file(file: &OsStr) -> Result<File, ParseFileError> {
-    let path = file.to_str().unwrap().to_string(); // todo handle
+    let path = get_path(file)?;
     if let Some(file_extension) = Path::new(file).extension().and_then(OsStr::to_str) {
         match file_extension {
             "las" => Ok(File {
@@ -30,3 +30,78 @@ pub fn parse_file(file: &OsStr) -> Result<File, ParseFileError> {
         )))
     }
 }
+
+pub fn parse_raster_file(file: &OsStr) -> Result<RasterFile, ParseFileError> {
+    let path = get_path(file)?;
+    if let Some(file_extension) = Path::new(file).extension().and_then(OsStr::to_str) {
+        match file_extension.to_lowercase().as_str() {
+            "tif" | "tiff" => Ok(RasterFile {
+                path,
+                file_type: RasterFileType::GeoTiff,
+            }),
+            "asc" => Ok(RasterFile {
+                path,
+                file_type: RasterFileType::EsriAscii,
+            }),
+            _ => Err(ParseFileError::UnsupportedFileType(format!(
+                "Unsupported raster file type \"{}\" of \"{}\" (supported are tif/tiff & asc)",
+                file_extension, path
+            ))),
+        }
+    } else {
+        Err(ParseFileError::NoFileType(format!(
+            "Cannot parse file type of: {}",
+            path
+        )))
+    }
+}
+
+fn get_path(file: &OsStr) -> Result<String, ParseFileError> {
+    file.to_str().map(str::to_string).ok_or_else(|| {
+        ParseFileError::InvalidPath(format!(
+            "File path \"{}\" is not valid UTF-8",
+            file.to_string_lossy()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_raster_file_types() {
+        assert!(matches!(
+            parse_raster_file(OsStr::new("dem.TIF")).unwrap().file_type,
+            RasterFileType::GeoTiff
+        ));
+        assert!(matches!(
+            parse_raster_file(OsStr::new("dem.asc")).unwrap().file_type,
+            RasterFileType::EsriAscii
+        ));
+        assert!(matches!(
+            parse_raster_file(OsStr::new("dem.png")),
+            Err(ParseFileError::UnsupportedFileType(_))
+        ));
+        assert!(matches!(
+            parse_raster_file(OsStr::new("dem")),
+            Err(ParseFileError::NoFileType(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let file = OsStr::from_bytes(b"cloud\xff.las");
+        assert!(matches!(
+            parse_file(file),
+            Err(ParseFileError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            parse_raster_file(OsStr::from_bytes(b"dem\xff.tif")),
+            Err(ParseFileError::InvalidPath(_))
+        ));
+    }
+}
@@ -1,10 +1,12 @@
 mod parsers;
 
-pub use self::parsers::parse_horizon;
+pub use self::parsers::{parse_cloud_horizon, parse_horizon, parse_horizon_step};
 
+/// Horizon height (degrees) in the azimuths (degrees from the north, clockwise, ascending),
+/// linearly interpolated between the azimuths and across the north
 #[derive(Debug)]
 pub struct Horizon {
-    pub angle_step: usize,
+    pub azimuths: Vec<f64>,
     pub horizon_height: Vec<f64>,
     pub is_flat: bool,
 }
@@ -12,7 +14,7 @@ pub struct Horizon {
 impl Default for Horizon {
     fn default() -> Self {
         Horizon {
-            angle_step: 360,
+            azimuths: vec![0.],
             horizon_height: vec![0.],
             is_flat: true,
         }
@@ -20,33 +22,156 @@ impl Default for Horizon {
 }
 
 impl Horizon {
+    /// Horizon from (azimuth, height) pairs in any order and with any steps
+    pub fn new(mut points: Vec<(f64, f64)>) -> Result<Horizon, String> {
+        if points.is_empty() {
+            return Err("Horizon has no values".to_string());
+        }
+
+        // profiles closed by the 360 azimuth repeat the north value
+        if let Some((_, north_height)) = points.iter().find(|(azimuth, _)| *azimuth == 0.) {
+            let north_height = *north_height;
+            points.retain(|(azimuth, height)| *azimuth != 360. || *height != north_height);
+        }
+
+        for (azimuth, height) in points.iter_mut() {
+            if !azimuth.is_finite() || !(0. ..=360.).contains(azimuth) {
+                return Err(format!(
+                    "Horizon azimuth {} is not in 0-360 degrees",
+                    azimuth
+                ));
+            }
+            if !height.is_finite() || !(-90. ..=90.).contains(height) {
+                return Err(format!(
+                    "Horizon height {} is not in -90-90 degrees",
+                    height
+                ));
+            }
+            *azimuth %= 360.;
+        }
+
+        points.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some(window) = points.windows(2).find(|window| window[0].0 == window[1].0) {
+            return Err(format!("Duplicate horizon azimuth {}", window[0].0));
+        }
+
+        let (azimuths, horizon_height): (Vec<f64>, Vec<f64>) = points.into_iter().unzip();
+        let is_flat = horizon_height.iter().all(|height| *height == 0.);
+
+        Ok(Horizon {
+            azimuths,
+            horizon_height,
+            is_flat,
+        })
+    }
+
+    /// Horizon heights starting from the north with the constant azimuth step
+    pub fn from_angle_step(angle_step: f64, horizon_height: Vec<f64>) -> Result<Horizon, String> {
+        if angle_step <= 0. {
+            return Err(format!("Horizon step {} must be positive", angle_step));
+        }
+        if angle_step * (horizon_height.len() as f64 - 1.) >= 360. {
+            return Err(format!(
+                "Horizon step {} with {} values exceeds 360 degrees",
+                angle_step,
+                horizon_height.len()
+            ));
+        }
+
+        let points = horizon_height
+            .into_iter()
+            .enumerate()
+            .map(|(idx, height)| (idx as f64 * angle_step, height))
+            .collect();
+        Horizon::new(points)
+    }
+
     pub fn is_visible(&self, azimuth: f64, altitude: f64) -> bool {
-        let azimuth = azimuth.to_degrees();
-        let altitude = altitude.to_degrees();
-        let angle_step = self.angle_step as f64;
         if self.is_flat {
-            true
+            return true;
+        }
+
+        let azimuth = azimuth.to_degrees().rem_euclid(360.);
+        let altitude = altitude.to_degrees();
+
+        let last_idx = self.azimuths.len() - 1;
+        let right_idx = self.azimuths.partition_point(|value| *value <= azimuth);
+        let (left_idx, right_idx) = match right_idx {
+            0 => (last_idx, 0),
+            idx if idx > last_idx => (last_idx, 0),
+            idx => (idx - 1, idx),
+        };
+
+        let left_height = self.horizon_height[left_idx];
+        let right_height = self.horizon_height[right_idx];
+
+        let span = (self.azimuths[right_idx] - self.azimuths[left_idx]).rem_euclid(360.);
+        let horizon_height = if span > 0. {
+            let azimuth_residual = (azimuth - self.azimuths[left_idx]).rem_euclid(360.);
+            left_height + (((right_height - left_height) / span) * azimuth_residual)
         } else {
-            let mut angle_idx = (azimuth / angle_step).floor() as usize;
-            let last_idx = self.horizon_height.len() - 1;
+            left_height
+        };
 
-            if angle_idx > last_idx {
-                angle_idx = last_idx;
-            };
+        altitude > horizon_height
+    }
+}
 
-            let left_height = self.horizon_height[angle_idx];
-            let right_height = if angle_idx == last_idx {
-                self.horizon_height[0]
-            } else {
-                self.horizon_height[angle_idx + 1]
-            };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let azimuth_residual = azimuth % angle_step;
+    fn is_visible(horizon: &Horizon, azimuth: f64, altitude: f64) -> bool {
+        horizon.is_visible(azimuth.to_radians(), altitude.to_radians())
+    }
 
-            let horizon_height =
-                left_height + (((right_height - left_height) / angle_step) * azimuth_residual);
+    #[test]
+    fn sorts_and_validates_points() {
+        let horizon = Horizon::new(vec![(180., 5.), (0., 1.), (90., 2.)]).unwrap();
+        assert_eq!(horizon.azimuths, vec![0., 90., 180.]);
+        assert_eq!(horizon.horizon_height, vec![1., 2., 5.]);
+        assert!(!horizon.is_flat);
 
-            altitude > horizon_height
-        }
+        assert!(Horizon::new(vec![]).is_err());
+        assert!(Horizon::new(vec![(400., 0.)]).is_err());
+        assert!(Horizon::new(vec![(0., 95.)]).is_err());
+        assert!(Horizon::new(vec![(90., 1.), (90., 2.)]).is_err());
+        assert!(Horizon::new(vec![(0., 0.), (0., 0.)]).is_err());
+    }
+
+    #[test]
+    fn drops_closing_north_value() {
+        let horizon = Horizon::new(vec![(0., 3.), (180., 1.), (360., 3.)]).unwrap();
+        assert_eq!(horizon.azimuths, vec![0., 180.]);
+
+        // different heights in the north are ambiguous
+        assert!(Horizon::new(vec![(0., 3.), (180., 1.), (360., 4.)]).is_err());
+
+        let horizon = Horizon::new(vec![(180., 1.), (360., 3.)]).unwrap();
+        assert_eq!(horizon.azimuths, vec![0., 180.]);
+    }
+
+    #[test]
+    fn interpolates_across_north() {
+        let horizon = Horizon::new(vec![(90., 0.), (270., 0.), (350., 10.), (10., 20.)]).unwrap();
+
+        // halfway between 350 & 10 degrees
+        assert!(!is_visible(&horizon, 0., 14.9));
+        assert!(is_visible(&horizon, 0., 15.1));
+        assert!(!is_visible(&horizon, 360., 14.9));
+        assert!(!is_visible(&horizon, -5., 12.));
+        assert!(is_visible(&horizon, 180., 0.1));
+
+        let horizon = Horizon::from_angle_step(90., vec![10., 0., 0., 0.]).unwrap();
+        // between 270 & 360 (north) degrees
+        assert!(!is_visible(&horizon, 315., 4.9));
+        assert!(is_visible(&horizon, 315., 5.1));
+    }
+
+    #[test]
+    fn flat_horizon_is_always_visible() {
+        let horizon = Horizon::default();
+        assert!(horizon.is_flat);
+        assert!(is_visible(&horizon, 123., -1.));
     }
 }
@@ -1,24 +1,50 @@
 use super::Horizon;
-use clap::Error;
 
-pub fn parse_horizon(input: &str) -> Result<Horizon, Error> {
-    let mut horizon_height = input
+pub fn parse_horizon(input: &str) -> Result<Horizon, String> {
+    let mut values = input
         .split(',')
-        .map(|str| str.parse::<f64>().unwrap())
-        .collect::<Vec<f64>>();
+        .map(|value| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("Cannot parse horizon value \"{}\"", value))
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
 
-    if horizon_height.len() < 2 {
-        Ok(Horizon::default())
-    } else {
-        let angle_step = horizon_height[0] as usize;
-        horizon_height.remove(0);
+    if values.len() < 2 {
+        return Err(
+            "Horizon requires angle step followed by at least one elevation value".to_string(),
+        );
+    }
 
-        let is_flat = horizon_height[0] == 0.;
+    let angle_step = values.remove(0);
+    Horizon::from_angle_step(angle_step, values)
+}
 
-        Ok(Horizon {
-            angle_step,
-            horizon_height,
-            is_flat,
-        })
+pub fn parse_horizon_step(input: &str) -> Result<usize, String> {
+    let angle_step = input
+        .parse::<usize>()
+        .map_err(|_| format!("Cannot parse horizon step \"{}\"", input))?;
+
+    if angle_step == 0 || 360 % angle_step != 0 {
+        return Err(format!(
+            "Horizon step {} (degrees) must divide 360",
+            angle_step
+        ));
+    }
+    Ok(angle_step)
+}
+
+pub fn parse_cloud_horizon(input: &str) -> Result<f64, String> {
+    let cell_size = input
+        .parse::<f64>()
+        .map_err(|_| format!("Cannot parse cloud horizon cell size \"{}\"", input))?;
+
+    if !cell_size.is_finite() || cell_size <= 0. {
+        return Err(format!(
+            "Cloud horizon cell size {} must be positive",
+            input
+        ));
     }
+    Ok(cell_size)
 }
@@ -1,6 +1,11 @@
+pub mod albedo;
 pub mod block_params;
 pub mod centroid;
+pub mod classes;
+pub mod diffuse_model;
 pub mod file;
 pub mod horizon;
 pub mod linke;
+pub mod shadow_method;
 pub mod time_range;
+pub mod weather;
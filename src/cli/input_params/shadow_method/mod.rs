@@ -0,0 +1,13 @@
+mod parsers;
+
+pub use self::parsers::{
+    parse_shadow_method, parse_shadow_samples, parse_shadow_supersampling, parse_sky_view_rays,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowMethod {
+    /// voxels projected to the plane perpendicular to the sun direction
+    IlluminationMap,
+    /// rays marched from the voxels towards the sun through the voxel grid
+    RayTracing,
+}
@@ -0,0 +1,43 @@
+use super::ShadowMethod;
+
+pub fn parse_shadow_method(input: &str) -> Result<ShadowMethod, String> {
+    match input {
+        "illumination-map" => Ok(ShadowMethod::IlluminationMap),
+        "ray-tracing" => Ok(ShadowMethod::RayTracing),
+        _ => Err("Invalid shadow method [Use illumination-map or ray-tracing]".to_string()),
+    }
+}
+
+pub fn parse_shadow_supersampling(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(supersampling) if supersampling > 0. => Ok(supersampling),
+        _ => Err("Invalid shadow supersampling [Use positive decimal]".to_string()),
+    }
+}
+
+pub fn parse_shadow_samples(input: &str) -> Result<usize, String> {
+    match input.parse::<usize>() {
+        Ok(samples) if samples > 0 => Ok(samples),
+        _ => Err("Invalid shadow samples [Use positive integer]".to_string()),
+    }
+}
+
+pub fn parse_sky_view_rays(input: &str) -> Result<usize, String> {
+    match input.parse::<usize>() {
+        Ok(rays) if rays > 0 => Ok(rays),
+        _ => Err("Invalid sky view rays [Use positive integer]".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sky_view_rays() {
+        assert_eq!(parse_sky_view_rays("64"), Ok(64));
+        for input in ["0", "-1", "1.5", ""] {
+            assert!(parse_sky_view_rays(input).is_err(), "{}", input);
+        }
+    }
+}
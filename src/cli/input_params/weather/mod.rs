@@ -0,0 +1,137 @@
+mod parsers;
+mod tmy;
+
+use chrono::{DateTime, Duration, Utc};
+
+pub use self::parsers::parse_weather_file;
+pub use self::tmy::{parse_tmy_file, Tmy};
+
+/// Measured irradiance [W/m2] on the horizontal plane (DNI normal to the sun)
+/// or the clear-sky index (measured / clear-sky global irradiance)
+#[derive(Debug, Clone)]
+pub struct WeatherRecord {
+    pub time: DateTime<Utc>,
+    pub ghi: Option<f64>,
+    pub dhi: Option<f64>,
+    pub dni: Option<f64>,
+    pub clear_sky_index: Option<f64>,
+    /// air temperature [°C]
+    pub temperature: Option<f64>,
+}
+
+/// Weather records sorted by time
+#[derive(Debug)]
+pub struct Weather(pub Vec<WeatherRecord>);
+
+impl Weather {
+    /// Time span of the records - each record stands for the time up to the half of the step
+    /// to its neighbour, the first & last records to the half of the step on both sides
+    pub fn get_time_span(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let records = &self.0;
+        let (first, last) = (&records[0], &records[records.len() - 1]);
+        match (
+            records.get(1),
+            records.len().checked_sub(2).map(|idx| &records[idx]),
+        ) {
+            (Some(second), Some(penultimate)) => (
+                first.time - (second.time - first.time) / 2,
+                last.time + (last.time - penultimate.time) / 2,
+            ),
+            _ => (first.time, last.time),
+        }
+    }
+
+    /// Shortest step between the records, none for a single record
+    pub fn get_record_step(&self) -> Option<Duration> {
+        self.0
+            .windows(2)
+            .map(|pair| pair[1].time - pair[0].time)
+            .filter(|step| *step > Duration::zero())
+            .min()
+    }
+
+    /// Record closest to the time, none outside of the time span of the records
+    /// or in the gaps of the records (nearest record more than a record step away)
+    pub fn get_record(&self, time: DateTime<Utc>) -> Option<&WeatherRecord> {
+        let records = &self.0;
+        let (from, to) = self.get_time_span();
+        if time < from || time > to {
+            return None;
+        }
+
+        let idx = records.partition_point(|record| record.time < time);
+        let record = match (
+            idx.checked_sub(1).map(|idx| &records[idx]),
+            records.get(idx),
+        ) {
+            (Some(before), Some(after)) if time - before.time < after.time - time => Some(before),
+            (_, Some(after)) => Some(after),
+            (before, None) => before,
+        }?;
+
+        match self.get_record_step() {
+            Some(step) if time - record.time > step || record.time - time > step => None,
+            _ => Some(record),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn get_weather(hours: &[u32]) -> Weather {
+        Weather(
+            hours
+                .iter()
+                .map(|hour| WeatherRecord {
+                    time: Utc.ymd(2020, 6, 21).and_hms(*hour, 0, 0),
+                    ghi: Some(*hour as f64),
+                    dhi: None,
+                    dni: None,
+                    clear_sky_index: None,
+                    temperature: None,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn gets_closest_record_within_time_span() {
+        let weather = get_weather(&[10, 11, 13]);
+        let time = |hour: u32, min: u32| Utc.ymd(2020, 6, 21).and_hms(hour, min, 0);
+
+        assert_eq!(weather.get_time_span(), (time(9, 30), time(14, 0)));
+        assert!(weather.get_record(time(9, 29)).is_none());
+        assert_eq!(weather.get_record(time(9, 30)).unwrap().ghi, Some(10.));
+        assert_eq!(weather.get_record(time(10, 29)).unwrap().ghi, Some(10.));
+        assert_eq!(weather.get_record(time(10, 31)).unwrap().ghi, Some(11.));
+        assert_eq!(weather.get_record(time(12, 1)).unwrap().ghi, Some(13.));
+        assert_eq!(weather.get_record(time(14, 0)).unwrap().ghi, Some(13.));
+        assert!(weather
+            .get_record(time(14, 0) + Duration::seconds(1))
+            .is_none());
+    }
+
+    #[test]
+    fn gets_no_record_in_gaps() {
+        let weather = get_weather(&[10, 11, 12, 16, 17]);
+        let time = |hour: u32, min: u32| Utc.ymd(2020, 6, 21).and_hms(hour, min, 0);
+
+        assert_eq!(weather.get_record_step(), Some(Duration::hours(1)));
+        assert_eq!(weather.get_record(time(13, 0)).unwrap().ghi, Some(12.));
+        assert!(weather.get_record(time(13, 1)).is_none());
+        assert!(weather.get_record(time(14, 0)).is_none());
+        assert!(weather.get_record(time(14, 59)).is_none());
+        assert_eq!(weather.get_record(time(15, 0)).unwrap().ghi, Some(16.));
+    }
+
+    #[test]
+    fn single_record_covers_its_time_only() {
+        let weather = get_weather(&[10]);
+        let time = Utc.ymd(2020, 6, 21).and_hms(10, 0, 0);
+        assert!(weather.get_record(time).is_some());
+        assert!(weather.get_record(time + Duration::minutes(1)).is_none());
+    }
+}
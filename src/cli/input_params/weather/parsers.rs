@@ -0,0 +1,158 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+
+use super::{Weather, WeatherRecord};
+
+/// CSV with header - time column (RFC3339 or "YYYY-MM-DD HH:MM[:SS]" in UTC) and the ghi, dhi,
+/// dni [W/m2] (ghi or dni with dhi) or kc (clear-sky index) columns, records without usable values are skipped
+pub fn parse_weather_file(input: &str) -> Result<Weather, String> {
+    let content = fs::read_to_string(input)
+        .map_err(|err| format!("Cannot read weather file \"{}\": {}", input, err))?;
+
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+
+    let separator = |char: char| char == ',' || char == ';';
+
+    let header = lines
+        .next()
+        .map(|(_, line)| {
+            line.split(separator)
+                .map(|column| column.trim().to_lowercase())
+                .collect::<Vec<String>>()
+        })
+        .ok_or("Empty weather file")?;
+
+    let find_column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|column| names.contains(&column.as_str()))
+    };
+    let time_column = find_column(&["time", "timestamp", "datetime"])
+        .ok_or("Weather file has no time column (time, timestamp or datetime)")?;
+    let ghi_column = find_column(&["ghi"]);
+    let dhi_column = find_column(&["dhi", "dif"]);
+    let dni_column = find_column(&["dni"]);
+    let clear_sky_index_column = find_column(&["kc", "clear_sky_index"]);
+    let temperature_column = find_column(&["temperature", "temp_air"]);
+
+    // dni alone cannot be split into the components
+    let has_dni_split = dni_column.is_some() && dhi_column.is_some();
+    if ghi_column.is_none() && !has_dni_split && clear_sky_index_column.is_none() {
+        return Err("Weather file requires ghi, dni & dhi or kc column".to_string());
+    }
+
+    let mut records = vec![];
+    for (line_idx, line) in lines {
+        let values: Vec<&str> = line.split(separator).map(str::trim).collect();
+
+        let time = values
+            .get(time_column)
+            .and_then(|value| parse_time(value))
+            .ok_or_else(|| format!("Invalid time on line {} of the weather file", line_idx + 1))?;
+
+        let get_value = |column: Option<usize>| -> Result<Option<f64>, String> {
+            match column.and_then(|column| values.get(column)) {
+                Some(value) if !value.is_empty() && !value.eq_ignore_ascii_case("nan") => {
+                    value.parse::<f64>().map(Some).map_err(|_| {
+                        format!(
+                            "Invalid value \"{}\" on line {} of the weather file",
+                            value,
+                            line_idx + 1
+                        )
+                    })
+                }
+                _ => Ok(None),
+            }
+        };
+
+        let record = WeatherRecord {
+            time,
+            ghi: get_value(ghi_column)?,
+            dhi: get_value(dhi_column)?,
+            dni: get_value(dni_column)?,
+            clear_sky_index: get_value(clear_sky_index_column)?,
+            temperature: get_value(temperature_column)?,
+        };
+
+        let is_usable = record.clear_sky_index.is_some()
+            || record.ghi.is_some()
+            || (record.dni.is_some() && record.dhi.is_some());
+        if is_usable {
+            records.push(record);
+        }
+    }
+
+    if records.is_empty() {
+        return Err(format!(
+            "No weather records with ghi, dni & dhi or kc values in \"{}\"",
+            input
+        ));
+    }
+
+    records.sort_by_key(|record| record.time);
+    Ok(Weather(records))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    value.parse::<DateTime<Utc>>().ok().or_else(|| {
+        [
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|time| DateTime::<Utc>::from_utc(time, Utc))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+    use chrono::TimeZone;
+
+    fn parse(content: &str) -> Result<Weather, String> {
+        let file = TestFile::with_content("csv", content);
+        parse_weather_file(file.path())
+    }
+
+    #[test]
+    fn parses_records_sorted_by_time() {
+        let weather = parse(
+            "# station 1\nTime;GHI;DHI;Temperature\n2020-06-21 11:00;700;200;25\n2020-06-21T10:00:00Z;600;nan;24\n2020-06-21 12:00;;;26\n",
+        )
+        .unwrap();
+
+        let records = &weather.0;
+        // record without any value is skipped
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].time, Utc.ymd(2020, 6, 21).and_hms(10, 0, 0));
+        assert_eq!(records[0].ghi, Some(600.));
+        assert_eq!(records[0].dhi, None);
+        assert_eq!(records[1].dhi, Some(200.));
+        assert_eq!(records[1].temperature, Some(25.));
+    }
+
+    #[test]
+    fn requires_dhi_with_dni() {
+        let err = parse("time,dni\n2020-06-21 11:00,700\n").unwrap_err();
+        assert!(err.contains("requires"));
+
+        let weather =
+            parse("time,dni,dhi\n2020-06-21 11:00,700,100\n2020-06-21 12:00,700,\n").unwrap();
+        assert_eq!(weather.0.len(), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert!(parse("date,ghi\n2020-06-21,700\n").is_err());
+        assert!(parse("time,ghi\nnoon,700\n").is_err());
+        assert!(parse("time,ghi\n2020-06-21 11:00,x\n").is_err());
+        assert!(parse("time,kc\n2020-06-21 11:00,\n").is_err());
+    }
+}
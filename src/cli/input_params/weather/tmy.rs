@@ -0,0 +1,372 @@
+// EPW: https://bigladdersoftware.com/epx/docs/8-3/auxiliary-programs/energyplus-weather-file-epw-data-dictionary.html
+// TMY3: https://www.nrel.gov/docs/fy08osti/43156.pdf
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::fs;
+
+use super::{Weather, WeatherRecord};
+use crate::cli::input_params::centroid::Centroid;
+
+/// Typical meteorological year - site and hourly records in the local standard time
+#[derive(Debug)]
+pub struct Tmy {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: f64,
+    /// hours from UTC
+    pub time_zone: f64,
+    pub records: Vec<TmyRecord>,
+}
+
+/// Hourly values, the hour (1-24) is the end of the hour interval
+#[derive(Debug)]
+pub struct TmyRecord {
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub ghi: Option<f64>,
+    pub dhi: Option<f64>,
+    pub dni: Option<f64>,
+    pub temperature: Option<f64>,
+}
+
+impl Tmy {
+    pub fn get_centroid(&self) -> Centroid {
+        Centroid {
+            lat: self.latitude,
+            lon: self.longitude,
+            elevation: self.elevation,
+        }
+    }
+
+    /// Records of the typical year placed in the year, in the middle of their hour interval
+    pub fn to_weather(&self, year: i32) -> Weather {
+        let time_zone = Duration::seconds((self.time_zone * 3600.) as i64);
+
+        let records = self
+            .records
+            .iter()
+            .filter_map(|record| {
+                // February 29 of the typical year doesn't exist in the common years
+                let date = Utc.ymd_opt(year, record.month, record.day).single()?;
+                let time = date.and_hms(0, 0, 0) + Duration::hours(record.hour as i64)
+                    - Duration::minutes(30)
+                    - time_zone;
+                Some(WeatherRecord {
+                    time,
+                    ghi: record.ghi,
+                    dhi: record.dhi,
+                    dni: record.dni,
+                    clear_sky_index: None,
+                    temperature: record.temperature,
+                })
+            })
+            .collect::<Vec<WeatherRecord>>();
+
+        let mut weather = Weather(records);
+        weather.0.sort_by_key(|record| record.time);
+        weather
+    }
+
+    /// Time span of the hour intervals of the year (UTC)
+    pub fn get_time_span(&self, year: i32) -> (DateTime<Utc>, DateTime<Utc>) {
+        let time_zone = Duration::seconds((self.time_zone * 3600.) as i64);
+        let from = Utc.ymd(year, 1, 1).and_hms(0, 0, 0) - time_zone;
+        let to = Utc.ymd(year + 1, 1, 1).and_hms(0, 0, 0) - time_zone;
+        (from, to)
+    }
+}
+
+pub fn parse_tmy_file(input: &str) -> Result<Tmy, String> {
+    let content = fs::read_to_string(input)
+        .map_err(|err| format!("Cannot read TMY file \"{}\": {}", input, err))?;
+
+    let tmy = if input.to_lowercase().ends_with(".epw") {
+        parse_epw(&content)?
+    } else {
+        parse_tmy3(&content)?
+    };
+
+    if tmy.records.is_empty() {
+        return Err(format!("No hourly records in \"{}\"", input));
+    }
+    Ok(tmy)
+}
+
+fn parse_epw(content: &str) -> Result<Tmy, String> {
+    let mut lines = content.lines();
+
+    // LOCATION,City,State,Country,Source,WMO,Latitude,Longitude,TimeZone,Elevation
+    let location: Vec<&str> = lines
+        .next()
+        .filter(|line| line.starts_with("LOCATION"))
+        .ok_or("EPW file has to start with the LOCATION line")?
+        .split(',')
+        .collect();
+    let get_location_value = |idx: usize, name: &str| {
+        location
+            .get(idx)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .ok_or_else(|| format!("Invalid {} in the EPW LOCATION line", name))
+    };
+
+    let latitude = get_location_value(6, "latitude")?;
+    let longitude = get_location_value(7, "longitude")?;
+    let time_zone = get_location_value(8, "time zone")?;
+    let elevation = get_location_value(9, "elevation")?;
+
+    // the rest of the header (design conditions, ..., data periods) precedes the hourly data
+    let records = lines
+        .enumerate()
+        .skip(7)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_idx, line)| {
+            let values: Vec<&str> = line.split(',').collect();
+            let get_value = |idx: usize| {
+                values
+                    .get(idx)
+                    .and_then(|value| value.trim().parse::<f64>().ok())
+            };
+            // missing values
+            let get_irradiance = |idx: usize| get_value(idx).filter(|value| *value < 9999.);
+
+            match (get_value(1), get_value(2), get_value(3)) {
+                (Some(month), Some(day), Some(hour)) => Ok(TmyRecord {
+                    month: month as u32,
+                    day: day as u32,
+                    hour: hour as u32,
+                    ghi: get_irradiance(13),
+                    dni: get_irradiance(14),
+                    dhi: get_irradiance(15),
+                    temperature: get_value(6).filter(|value| *value < 99.9),
+                }),
+                _ => Err(format!("Invalid EPW data on line {}", line_idx + 2)),
+            }
+        })
+        .collect::<Result<Vec<TmyRecord>, String>>()?;
+
+    Ok(Tmy {
+        latitude,
+        longitude,
+        elevation,
+        time_zone,
+        records,
+    })
+}
+
+fn parse_tmy3(content: &str) -> Result<Tmy, String> {
+    let mut lines = content.lines();
+
+    // USAF,Site name,State,TZ,latitude,longitude,elevation
+    let site: Vec<&str> = lines.next().ok_or("Empty TMY3 file")?.split(',').collect();
+    let get_site_value = |idx: usize, name: &str| {
+        site.get(idx)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .ok_or_else(|| format!("Invalid {} in the TMY3 site line", name))
+    };
+
+    let time_zone = get_site_value(3, "time zone")?;
+    let latitude = get_site_value(4, "latitude")?;
+    let longitude = get_site_value(5, "longitude")?;
+    let elevation = get_site_value(6, "elevation")?;
+
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or("TMY3 file has no header line")?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let find_column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column.starts_with(name))
+            .ok_or_else(|| format!("TMY3 file has no \"{}\" column", name))
+    };
+
+    let date_column = find_column("Date")?;
+    let time_column = find_column("Time")?;
+    let ghi_column = find_column("GHI (W/m^2)")?;
+    let dni_column = find_column("DNI (W/m^2)")?;
+    let dhi_column = find_column("DHI (W/m^2)")?;
+    let temperature_column = find_column("Dry-bulb")?;
+
+    let records = lines
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_idx, line)| {
+            let values: Vec<&str> = line.split(',').map(str::trim).collect();
+            let get_value =
+                |idx: usize| values.get(idx).and_then(|value| value.parse::<f64>().ok());
+            let invalid_line = || format!("Invalid TMY3 data on line {}", line_idx + 3);
+
+            // MM/DD/YYYY & HH:MM
+            let date: Vec<u32> = values
+                .get(date_column)
+                .map(|date| {
+                    date.split('/')
+                        .filter_map(|value| value.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let hour = values
+                .get(time_column)
+                .and_then(|time| time.split(':').next())
+                .and_then(|hour| hour.parse::<u32>().ok());
+
+            match (date.as_slice(), hour) {
+                ([month, day, _], Some(hour)) => Ok(TmyRecord {
+                    month: *month,
+                    day: *day,
+                    hour,
+                    ghi: get_value(ghi_column),
+                    dni: get_value(dni_column),
+                    dhi: get_value(dhi_column),
+                    temperature: get_value(temperature_column),
+                }),
+                _ => Err(invalid_line()),
+            }
+        })
+        .collect::<Result<Vec<TmyRecord>, String>>()?;
+
+    Ok(Tmy {
+        latitude,
+        longitude,
+        elevation,
+        time_zone,
+        records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPW_HEADER: &str = "LOCATION,Bratislava,-,SVK,IWEC,118160,48.17,17.20,1.0,130.0
+DESIGN CONDITIONS,0
+TYPICAL/EXTREME PERIODS,0
+GROUND TEMPERATURES,0
+HOLIDAYS/DAYLIGHT SAVINGS,No,0,0,0
+COMMENTS 1,x
+COMMENTS 2,y
+DATA PERIODS,1,1,Data,Sunday, 1/ 1,12/31
+";
+
+    fn get_epw_line(month: u32, day: u32, hour: u32, temperature: f64, ghi: f64) -> String {
+        format!(
+            "1995,{},{},{},60,x,{},0,0,0,0,0,0,{},100,9999,0,0,0,0,0\n",
+            month, day, hour, temperature, ghi
+        )
+    }
+
+    #[test]
+    fn parses_epw_site_and_records() {
+        let content = format!(
+            "{}{}{}",
+            EPW_HEADER,
+            get_epw_line(1, 1, 1, 99.9, 0.),
+            get_epw_line(6, 21, 12, 21.5, 800.)
+        );
+        let tmy = parse_epw(&content).unwrap();
+
+        assert_eq!(
+            (tmy.latitude, tmy.longitude, tmy.time_zone, tmy.elevation),
+            (48.17, 17.2, 1., 130.)
+        );
+        assert_eq!(tmy.records.len(), 2);
+        let record = &tmy.records[1];
+        assert_eq!((record.month, record.day, record.hour), (6, 21, 12));
+        assert_eq!(
+            (record.ghi, record.dni, record.dhi),
+            (Some(800.), Some(100.), None)
+        );
+        assert_eq!(record.temperature, Some(21.5));
+        // 99.9 marks the missing temperature
+        assert_eq!(tmy.records[0].temperature, None);
+    }
+
+    #[test]
+    fn parses_tmy3_site_and_records() {
+        let content = "118160,\"BRATISLAVA\",SK,1.0,48.17,17.20,130
+Date (MM/DD/YYYY),Time (HH:MM),ETR (W/m^2),ETRN (W/m^2),GHI (W/m^2),GHI source,GHI uncert (%),DNI (W/m^2),DNI source,DNI uncert (%),DHI (W/m^2),DHI source,DHI uncert (%),Dry-bulb (C),Dry-bulb source
+06/21/1995,12:00,0,0,800,1,0,600,1,0,150,1,0,21.5,A
+06/21/1995,24:00,0,0,0,1,0,0,1,0,0,1,0,14.2,A
+";
+        let tmy = parse_tmy3(content).unwrap();
+
+        assert_eq!((tmy.latitude, tmy.time_zone), (48.17, 1.));
+        let record = &tmy.records[0];
+        assert_eq!((record.month, record.day, record.hour), (6, 21, 12));
+        assert_eq!(
+            (record.ghi, record.dni, record.dhi),
+            (Some(800.), Some(600.), Some(150.))
+        );
+        assert_eq!(record.temperature, Some(21.5));
+        assert_eq!(tmy.records[1].hour, 24);
+
+        assert!(parse_tmy3("118160,\"BRATISLAVA\",SK,1.0,48.17,17.20,130\nDate,Time\n").is_err());
+        // dry-bulb temperature column is required
+        let header_end = content.find(",Dry-bulb (C)").unwrap();
+        let line_end = content.find("\n06/21").unwrap();
+        let without_temperature = format!("{}{}", &content[..header_end], &content[line_end..]);
+        assert_eq!(
+            parse_tmy3(&without_temperature).err(),
+            Some("TMY3 file has no \"Dry-bulb\" column".to_string())
+        );
+    }
+
+    fn get_tmy(time_zone: f64, records: &[(u32, u32, u32)]) -> Tmy {
+        Tmy {
+            latitude: 48.17,
+            longitude: 17.2,
+            elevation: 130.,
+            time_zone,
+            records: records
+                .iter()
+                .map(|(month, day, hour)| TmyRecord {
+                    month: *month,
+                    day: *day,
+                    hour: *hour,
+                    ghi: Some(100.),
+                    dhi: None,
+                    dni: None,
+                    temperature: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn shifts_hour_ending_local_time_to_utc() {
+        // hour 1 stands for 00:00-01:00 of the local standard time (UTC+1)
+        let weather = get_tmy(1., &[(1, 1, 1), (12, 31, 24), (6, 21, 13)]).to_weather(2021);
+        let times: Vec<DateTime<Utc>> = weather.0.iter().map(|record| record.time).collect();
+        assert_eq!(
+            times,
+            vec![
+                Utc.ymd(2020, 12, 31).and_hms(23, 30, 0),
+                Utc.ymd(2021, 6, 21).and_hms(11, 30, 0),
+                Utc.ymd(2021, 12, 31).and_hms(22, 30, 0),
+            ]
+        );
+
+        let weather = get_tmy(-5., &[(1, 1, 1)]).to_weather(2021);
+        assert_eq!(weather.0[0].time, Utc.ymd(2021, 1, 1).and_hms(5, 30, 0));
+    }
+
+    #[test]
+    fn drops_february_29_in_common_years() {
+        let tmy = get_tmy(0., &[(2, 28, 12), (2, 29, 12), (3, 1, 12)]);
+        assert_eq!(tmy.to_weather(2021).0.len(), 2);
+
+        let weather = tmy.to_weather(2020);
+        assert_eq!(weather.0.len(), 3);
+        assert_eq!(weather.0[1].time, Utc.ymd(2020, 2, 29).and_hms(11, 30, 0));
+    }
+
+    #[test]
+    fn time_span_covers_local_year() {
+        let (from, to) = get_tmy(1., &[]).get_time_span(2021);
+        assert_eq!(from, Utc.ymd(2020, 12, 31).and_hms(23, 0, 0));
+        assert_eq!(to, Utc.ymd(2021, 12, 31).and_hms(23, 0, 0));
+    }
+}
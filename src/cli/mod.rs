@@ -1,9 +1,19 @@
+use self::input_params::albedo::{parse_albedo, Albedo};
 use self::input_params::block_params::{parse_block_params, BlockParams};
 use self::input_params::centroid::{parse_centroid, Centroid};
-use self::input_params::file::{parse_file, File};
-use self::input_params::horizon::{parse_horizon, Horizon};
+use self::input_params::classes::{parse_classes, Classes};
+use self::input_params::diffuse_model::{parse_diffuse_model, DiffuseModel};
+use self::input_params::file::{parse_file, parse_raster_file, File, RasterFile};
+use self::input_params::horizon::{
+    parse_cloud_horizon, parse_horizon, parse_horizon_step, Horizon,
+};
 use self::input_params::linke::{parse_linke, Linke};
+use self::input_params::shadow_method::{
+    parse_shadow_method, parse_shadow_samples, parse_shadow_supersampling, parse_sky_view_rays,
+    ShadowMethod,
+};
 use self::input_params::time_range::{parse_time_range, TimeRange};
+use self::input_params::weather::{parse_tmy_file, parse_weather_file, Tmy, Weather};
 
 use clap::{AppSettings, Parser};
 
@@ -14,26 +24,122 @@ pub mod input_params;
 #[clap(name="Point Cloud Solar Radiation Tool", author, version, about, long_about = None)]
 #[clap(global_setting(AppSettings::DeriveDisplayOrder))]
 pub struct InputParams {
-    /// [<LAT(decimal)>,<LON(decimal)>,<ELEVATION(decimal)>] Point cloud centroid geographical coordinates & elevation
+    /// [<LAT(decimal)>,<LON(decimal)>,<ELEVATION(decimal)>] Point cloud centroid geographical coordinates & elevation (if not specified, derived from the CRS of the las/laz input file)
     #[clap(short, long, parse(try_from_str=parse_centroid))]
-    pub centroid: Centroid,
+    pub centroid: Option<Centroid>,
 
-    /// [<FROM(2020-01-01T12:00:00.000Z)>,<TO(2020-03-23T18:00:00.000Z)>] Time range in RFC3339 format
-    #[clap(short, long, parse(try_from_str=parse_time_range))]
-    pub time_range: TimeRange,
+    /// [<FROM(2020-01-01T12:00:00.000Z)>,<TO(2020-03-23T18:00:00.000Z)>] Time range in RFC3339 format (if not specified, the whole typical year of the TMY file)
+    #[clap(short, long, parse(try_from_str=parse_time_range), required_unless_present = "tmy")]
+    pub time_range: Option<TimeRange>,
 
     /// [<int>] Step in minutes used in time range
     #[clap(short, long)]
     pub step_mins: f64,
 
+    /// [<decimal>] Meridian convergence in degrees - angle from the true north to the grid north (y axis) of the cloud, clockwise (if not specified, derived from the CRS of the las/laz input file, otherwise 0)
+    #[clap(long, allow_hyphen_values = true)]
+    pub meridian_convergence: Option<f64>,
+
+    /// [<decimal>] Vertical offset in meters added to the point elevations (e.g. geoid undulation to get the elevation above sea level from ellipsoidal heights)
+    #[clap(long, default_value = "0", allow_hyphen_values = true)]
+    pub elevation_offset: f64,
+
+    /// Use the centroid elevation for all the voxels instead of their own elevation (e.g. if the cloud is in local coordinates)
+    #[clap(long)]
+    pub centroid_elevation: bool,
+
     /// [<SINGLE_LINKE(decimal)>] or [<MONTHLY_LINKE(12 comma separated decimals)>] Linke turbidity factor - single value or 12 (monthly) values
     #[clap(short, long, parse(try_from_str=parse_linke))]
     pub linke_turbidity_factor: Linke,
 
+    /// [<FILE>] CSV weather time series (time column & ghi, dhi, dni [W/m2] or kc clear-sky index columns) used to scale the clear-sky beam & diffuse components to the measured conditions
+    #[clap(long = "weather-file", value_name = "WEATHER_FILE", parse(try_from_str=parse_weather_file))]
+    pub weather: Option<Weather>,
+
+    /// [<FILE>] Typical meteorological year (EnergyPlus epw or TMY3 csv) - hourly irradiance used as the weather time series, the site location used as the centroid if it cannot be derived from the input file
+    #[clap(long = "tmy-file", value_name = "TMY_FILE", parse(try_from_str=parse_tmy_file), conflicts_with = "weather")]
+    pub tmy: Option<Tmy>,
+
+    /// [<MODEL>] Diffuse sky model used on the tilted surfaces (esra, isotropic, hay-davies or perez). ESRA is computed from the Linke turbidity factor, the other models from the horizontal diffuse & the beam normal irradiance (clear-sky or weather scaled)
+    #[clap(long, parse(try_from_str=parse_diffuse_model), default_value = "esra")]
+    pub diffuse_model: DiffuseModel,
+
+    /// [<SINGLE_ALBEDO(decimal)>] or [<MONTHLY_ALBEDO(12 comma separated decimals)>] or [<CLASS(int)>:<ALBEDO(decimal)>,..] Ground albedo used for the reflected component - single value, 12 (monthly) values or values of the classes of the lowest point in the voxel column - e.g. the roof itself where the scan has no ground below it (if not specified, the reflected component is not computed)
+    #[clap(long, parse(try_from_str=parse_albedo))]
+    pub albedo: Option<Albedo>,
+
     /// [<ANGLE_STEP(int)>,<ELEVATION(comma separated decimals - horizon elevation values)>] Horizon height used to take in account surrounding horizon (hills) when modeling solar radiation in smaller areas. Starts from north.
     #[clap(short, long, parse(try_from_str=parse_horizon), default_value="360,0")]
     pub horizon: Horizon,
 
+    /// [<FILE>] Text file with the horizon - <AZIMUTH(decimal)>,<ELEVATION(decimal)> pair in degrees on each line, azimuth from north clockwise with any steps (e.g. GRASS r.horizon output with -c -d flags)
+    #[clap(long, conflicts_with = "horizon")]
+    pub horizon_file: Option<String>,
+
+    /// [<FILE>] DEM raster (GeoTIFF or ESRI ASCII grid in the CRS of the cloud) used to compute the horizon around the cloud instead of the --horizon values
+    #[clap(long, parse(try_from_os_str=parse_raster_file), conflicts_with_all = &["horizon", "horizon-file"])]
+    pub horizon_dem: Option<RasterFile>,
+
+    /// [<decimal>] Cell size in meters of the height grid of the cloud used to compute the horizon of each block from the points outside of the block and its overlap (requires block processing)
+    #[clap(long, parse(try_from_str=parse_cloud_horizon), requires = "block-process-params")]
+    pub cloud_horizon: Option<f64>,
+
+    /// [<int>] Azimuth step in degrees of the horizon computed from the DEM or the cloud
+    #[clap(long, parse(try_from_str=parse_horizon_step), default_value = "1")]
+    pub horizon_step: usize,
+
+    /// [<decimal>] Search distance in meters of the horizon computed from the DEM or the cloud (if not specified, up to the raster edge)
+    #[clap(long)]
+    pub horizon_distance: Option<f64>,
+
+    /// [<illumination-map|ray-tracing>] Method used to find the voxels in shadow - voxels projected to the plane perpendicular to the sun direction or rays traced from each voxel towards the sun (slower)
+    #[clap(long, parse(try_from_str=parse_shadow_method), default_value = "illumination-map")]
+    pub shadow_method: ShadowMethod,
+
+    /// [<decimal>] Resolution of the illumination map - cells per voxel size in the plane perpendicular to the sun direction
+    #[clap(long, parse(try_from_str=parse_shadow_supersampling), default_value = "2")]
+    pub shadow_supersampling: f64,
+
+    /// Splat each voxel over the cells of its footprint in the illumination map, so there are no gaps between the occluders when the sun is not aligned with the grid axes
+    #[clap(long)]
+    pub shadow_splatting: bool,
+
+    /// [<int>] Number of shadow samples per voxel side - n x n sub-rays (ray-tracing) or footprint cells (splatted illumination map) are tested and the lit fraction scales the beam component and sun hours (requires ray-tracing or shadow splatting)
+    #[clap(long, parse(try_from_str=parse_shadow_samples), default_value = "1")]
+    pub shadow_samples: usize,
+
+    /// [<decimal>] Extinction coefficient of the vegetation (e.g. 0.5 for spherical leaf angle distribution) - the occluding voxels attenuate the beam by the Beer-Lambert law instead of blocking it (requires ray-tracing shadow method)
+    #[clap(long)]
+    pub extinction_coefficient: Option<f64>,
+
+    /// [<decimal>] Leaf area density [m2/m3] of a voxel with the average point count, the density of each voxel is scaled by its point count
+    #[clap(long, default_value = "1", requires = "extinction-coefficient")]
+    pub leaf_area_density: f64,
+
+    /// Compute the sky view factor of the voxels by casting rays over the hemisphere through the voxel grid, the diffuse component is reduced by the obstructed part of the sky
+    #[clap(long)]
+    pub sky_view_factor: bool,
+
+    /// [<int>] Number of rays over the hemisphere used for the sky view factor (positive)
+    #[clap(long, parse(try_from_str=parse_sky_view_rays), default_value = "64", requires = "sky-view-factor")]
+    pub sky_view_rays: usize,
+
+    /// [<int,int,...>] LAS classes of the points removed from the processing and the output (e.g. 7,18 for noise)
+    #[clap(long, parse(try_from_str=parse_classes))]
+    pub drop_classes: Option<Classes>,
+
+    /// [<int,int,...>] LAS classes of the points casting shadows (if not specified, all the points cast shadows)
+    #[clap(long, parse(try_from_str=parse_classes))]
+    pub occluder_classes: Option<Classes>,
+
+    /// [<int,int,...>] LAS classes of the vegetation points - voxels occluding only by vegetation attenuate the beam when using extinction coefficient, other voxels block it
+    #[clap(long, parse(try_from_str=parse_classes), default_value = "3,4,5")]
+    pub vegetation_classes: Classes,
+
+    /// [<int,int,...>] LAS classes of the points receiving the solar radiation, only these points are written to the output (if not specified, all the points)
+    #[clap(long, parse(try_from_str=parse_classes))]
+    pub output_classes: Option<Classes>,
+
     /// [<decimal>] Size of the voxel in meters
     #[clap(short, long)]
     pub voxel_size: Option<f64>,
@@ -46,11 +152,35 @@ pub struct InputParams {
     #[clap(short='b', long, parse(try_from_str=parse_block_params))]
     pub block_process_params: Option<BlockParams>,
 
+    /// [<FILE>,...] Point clouds (las/laz/ply) of the surroundings casting shadows on the input cloud - used only as occluders and not written to the output (e.g. neighbourhood of a building)
+    #[clap(long, parse(try_from_os_str=parse_file), multiple_occurrences = true, use_value_delimiter = true)]
+    pub occluders: Vec<File>,
+
+    /// [<decimal>] Maximal length (meters) of the shadow halo added to the blocks on the sunward side to include the occluders outside the block overlap (if not specified, covers the shadows of the sun higher than 5° and is limited by the cloud extent)
+    #[clap(long)]
+    pub max_shadow_halo: Option<f64>,
+
+    /// [<int>] Number of blocks processed concurrently when using block processing
+    #[clap(long, default_value = "1")]
+    pub concurrent_blocks: usize,
+
+    /// [<int>] Memory budget in MB, limits the number of concurrently processed blocks based on the largest block
+    #[clap(long)]
+    pub memory_budget: Option<usize>,
+
     /// When using ply output, specify if using text or binary format
     #[clap(long)]
     pub output_ply_ascii: bool,
 
-    /// Input file (las/laz)
+    /// Write the voxel normal as nx, ny, nz fields (otherwise it is stored as RGB in las output if the input has no color)
+    #[clap(long)]
+    pub output_normals: bool,
+
+    /// Write the count of points used to construct the voxel normal and their curvature (surface variation) as normal_point_count & normal_curvature fields
+    #[clap(long, requires = "output-normals")]
+    pub output_normal_quality: bool,
+
+    /// Input file (las/laz/ply)
     #[clap(parse(try_from_os_str=parse_file))]
     pub input_file: File,
 
@@ -58,3 +188,100 @@ pub struct InputParams {
     #[clap(parse(try_from_os_str=parse_file))]
     pub output_file: File,
 }
+
+impl InputParams {
+    /// Centroid specified by the user or derived from the input file CRS
+    pub fn get_centroid(&self) -> &Centroid {
+        self.centroid
+            .as_ref()
+            .expect("Centroid has to be resolved before the computation")
+    }
+
+    pub fn get_time_range(&self) -> &TimeRange {
+        self.time_range
+            .as_ref()
+            .expect("Time range has to be resolved before the computation")
+    }
+
+    pub fn is_dropped(&self, classification: Option<u8>) -> bool {
+        self.drop_classes
+            .as_ref()
+            .is_some_and(|classes| classes.contains(classification))
+    }
+
+    pub fn is_occluder(&self, classification: Option<u8>) -> bool {
+        self.occluder_classes
+            .as_ref()
+            .is_none_or(|classes| classes.contains(classification))
+    }
+
+    pub fn is_receiver(&self, classification: Option<u8>) -> bool {
+        self.output_classes
+            .as_ref()
+            .is_none_or(|classes| classes.contains(classification))
+    }
+
+    /// Points written to the output - not dropped and receiving
+    pub fn is_output_point(&self, classification: Option<u8>) -> bool {
+        !self.is_dropped(classification) && self.is_receiver(classification)
+    }
+}
+
+#[cfg(test)]
+impl InputParams {
+    /// Params of the tests at 48.7 N, 19.1 E, on June 21 with an hour step unless specified
+    pub fn from_test_args(args: &[&str]) -> Self {
+        let mut all_args = vec!["pcsrt", "-c", "48.7,19.1,200", "-l", "3"];
+        if !args.contains(&"-t") {
+            all_args.extend(["-t", "2020-06-21T00:00:00Z,2020-06-22T00:00:00Z"]);
+        }
+        if !args.contains(&"-s") {
+            all_args.extend(["-s", "60"]);
+        }
+        all_args.extend(args);
+        all_args.extend(["in.las", "out.las"]);
+        InputParams::parse_from(all_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes_select_point_roles() {
+        let input_params = InputParams::from_test_args(&[
+            "--drop-classes",
+            "7,18",
+            "--occluder-classes",
+            "2,6",
+            "--output-classes",
+            "2,6,7",
+        ]);
+        assert!(input_params.is_dropped(Some(7)));
+        assert!(!input_params.is_dropped(Some(2)));
+        assert!(!input_params.is_dropped(None));
+
+        assert!(input_params.is_occluder(Some(6)));
+        assert!(!input_params.is_occluder(Some(5)));
+        assert!(!input_params.is_occluder(None));
+
+        assert!(input_params.is_receiver(Some(7)));
+        assert!(!input_params.is_receiver(None));
+        // dropped classes are not written even if listed in the output classes
+        assert!(input_params.is_output_point(Some(2)));
+        assert!(!input_params.is_output_point(Some(7)));
+        assert!(!input_params.is_output_point(Some(5)));
+    }
+
+    #[test]
+    fn all_points_take_part_without_classes() {
+        let input_params = InputParams::from_test_args(&[]);
+        for classification in [None, Some(2), Some(7)] {
+            assert!(!input_params.is_dropped(classification));
+            assert!(input_params.is_occluder(classification));
+            assert!(input_params.is_receiver(classification));
+            assert!(input_params.is_output_point(classification));
+        }
+    }
+}
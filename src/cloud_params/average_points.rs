@@ -4,7 +4,7 @@ use rayon::iter::{ParallelBridge, ParallelIterator};
 use twox_hash::XxHash64;
 
 use crate::{
-    cli::input_params::block_params::BlockParams,
+    cli::{input_params::block_params::BlockParams, InputParams},
     common::Extent,
     io::Reader,
     voxel::{get_voxel_block_iterator, IntoVoxelKey},
@@ -15,16 +15,19 @@ pub fn get_average_points_in_voxel(
     extent: &Extent<f64>,
     block_size: usize,
     voxel_size: f64,
-) -> f64 {
+    input_params: &InputParams,
+) -> Result<f64, String> {
     let block_params = BlockParams {
         overlap: 0,
         size: block_size,
+        shadow_halo: None,
     };
-    let block_iterator = get_voxel_block_iterator(reader, extent, block_params);
+    let block_iterator = get_voxel_block_iterator(reader, &[], extent, block_params, input_params);
 
     let counter = block_iterator
         .par_bridge()
         .map(|block| {
+            let block = block?;
             let mut voxel_map: HashSet<(i64, i64, i64), BuildHasherDefault<XxHash64>> =
                 HashSet::default();
             let mut point_count: usize = 0;
@@ -37,14 +40,14 @@ pub fn get_average_points_in_voxel(
                     voxel_count += 1;
                 };
             }
-            Counter {
+            Ok(Counter {
                 point_count,
                 voxel_count,
-            }
+            })
         })
-        .sum::<Counter>();
+        .sum::<Result<Counter, String>>()?;
 
-    (counter.point_count as f64) / (counter.voxel_count as f64)
+    Ok((counter.point_count as f64) / (counter.voxel_count as f64))
 }
 
 struct Counter {
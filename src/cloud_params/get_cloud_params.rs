@@ -1,8 +1,13 @@
+use log::info;
 use std::error::Error;
 
-use las::Read;
-
-use crate::{cli::InputParams, common::Extent, io::Reader};
+use crate::{
+    cli::{input_params::block_params::BlockParams, InputParams},
+    common::Extent,
+    io::{PointSource, Reader},
+    radiation::get_shadow_halo,
+    voxel::GetCoords,
+};
 
 use super::{
     average_points::get_average_points_in_voxel, voxel_size::get_voxel_size_and_average_points,
@@ -10,50 +15,131 @@ use super::{
 
 pub fn get_cloud_params(
     input_params: &InputParams,
-    reader: &Reader,
+    reader: &mut Reader,
+    occluder_readers: &mut [Reader],
 ) -> Result<CloudParams, Box<dyn Error>> {
-    let mut extent = Extent {
-        min: (f64::MAX, f64::MAX, f64::MAX),
-        max: (f64::MIN, f64::MIN, f64::MIN),
+    let point_source = reader.to_point_source()?;
+
+    let (extent, point_count) = match (point_source.extent(), point_source.point_count()) {
+        (Some(extent), Some(point_count)) => (extent, point_count),
+        _ => get_extent_and_point_count(point_source),
     };
 
-    let mut point_count = 0;
+    let mut block_params = input_params.block_process_params.clone().unwrap_or_default();
+    let (x_blocks, y_blocks) = block_params.get_block_counts(&extent);
 
-    for point in reader.to_point_reader().points().flatten() {
-        point_count += 1;
-        extent.update((point.x, point.y, point.z));
+    if x_blocks * y_blocks > 1 || !occluder_readers.is_empty() {
+        // the surroundings may cast longer shadows than the cloud itself
+        let mut shadow_extent = extent.clone();
+        for occluder_reader in occluder_readers.iter() {
+            let occluder_extent = get_point_source_extent(occluder_reader.to_point_source()?);
+            shadow_extent.update(occluder_extent.min);
+            shadow_extent.update(occluder_extent.max);
+        }
+
+        let shadow_halo = get_shadow_halo(input_params, &shadow_extent);
+        info!(
+            "Shadow halo of blocks (left, bottom, right, top): {:.1}, {:.1}, {:.1}, {:.1}",
+            shadow_halo.left, shadow_halo.bottom, shadow_halo.right, shadow_halo.top
+        );
+        block_params.shadow_halo = Some(shadow_halo);
     }
 
-    let block_size = input_params.block_process_params.clone().unwrap_or_default().size;
+    if !occluder_readers.is_empty() {
+        let halo = block_params.get_halo();
+        let crop_extent = Extent {
+            min: (
+                extent.min.0 - halo.left,
+                extent.min.1 - halo.bottom,
+                f64::MIN,
+            ),
+            max: (extent.max.0 + halo.right, extent.max.1 + halo.top, f64::MAX),
+        };
+        for occluder_reader in occluder_readers.iter_mut() {
+            occluder_reader.set_crop_extent(crop_extent.clone());
+        }
+    }
 
-    let (voxel_size, average_points_in_voxel) = if input_params.voxel_size.is_none() {
+    if x_blocks * y_blocks > 1 {
+        info!("Splitting cloud into {} blocks", x_blocks * y_blocks);
+        reader.build_block_index(&extent, &block_params)?;
+        for occluder_reader in occluder_readers.iter_mut() {
+            occluder_reader.build_block_index(&extent, &block_params)?;
+        }
+    }
+
+    let reader = &*reader;
+    let block_size = block_params.size;
+
+    let (voxel_size, average_points_in_voxel) = if let Some(voxel_size) = input_params.voxel_size {
+        let average_points_in_voxel =
+            get_average_points_in_voxel(reader, &extent, block_size, voxel_size, input_params)?;
+        (voxel_size, average_points_in_voxel)
+    } else {
         get_voxel_size_and_average_points(
             reader,
             &extent,
             block_size,
             input_params.average_points_in_voxel,
             0.5,
-        )
-    } else {
-        let voxel_size = input_params.voxel_size.unwrap();
-        let average_points_in_voxel =
-            get_average_points_in_voxel(reader, &extent, block_size, voxel_size);
-        (voxel_size, average_points_in_voxel)
+            input_params,
+        )?
     };
 
+    let output_point_count =
+        if input_params.drop_classes.is_some() || input_params.output_classes.is_some() {
+            get_output_point_count(reader.to_point_source()?, input_params)
+        } else {
+            point_count
+        };
+
     let cloud_params = CloudParams {
         voxel_size,
         average_points_in_voxel,
         point_count,
+        output_point_count,
         extent,
+        block_params,
     };
 
     Ok(cloud_params)
 }
 
+fn get_extent_and_point_count(point_source: Box<dyn PointSource>) -> (Extent<f64>, usize) {
+    let mut extent = Extent {
+        min: (f64::MAX, f64::MAX, f64::MAX),
+        max: (f64::MIN, f64::MIN, f64::MIN),
+    };
+
+    let mut point_count = 0;
+
+    for point in point_source {
+        point_count += 1;
+        extent.update((point.x(), point.y(), point.z()));
+    }
+
+    (extent, point_count)
+}
+
+fn get_point_source_extent(point_source: Box<dyn PointSource>) -> Extent<f64> {
+    match point_source.extent() {
+        Some(extent) => extent,
+        None => get_extent_and_point_count(point_source).0,
+    }
+}
+
+fn get_output_point_count(point_source: Box<dyn PointSource>, input_params: &InputParams) -> usize {
+    point_source
+        .filter(|point| input_params.is_output_point(point.attributes.classification))
+        .count()
+}
+
 pub struct CloudParams {
     pub voxel_size: f64,
     pub average_points_in_voxel: f64,
     pub point_count: usize,
+    /// points written to the output (without the dropped classes & with the output classes only)
+    pub output_point_count: usize,
     pub extent: Extent<f64>,
+    pub block_params: BlockParams,
 }
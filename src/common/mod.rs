@@ -1,4 +1,8 @@
 pub mod constants;
 mod extent;
+#[cfg(test)]
+mod test_file;
 
 pub use self::extent::Extent;
+#[cfg(test)]
+pub use self::test_file::TestFile;
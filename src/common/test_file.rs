@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Temporary file of a test, unique per process and test, removed when dropped (also on panic)
+pub struct TestFile {
+    path: PathBuf,
+}
+
+impl TestFile {
+    pub fn new(extension: &str) -> Self {
+        let file_number = FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "pcsrt-test-{}-{}.{}",
+            process::id(),
+            file_number,
+            extension
+        ));
+        TestFile { path }
+    }
+    pub fn with_content(extension: &str, content: impl AsRef<[u8]>) -> Self {
+        let file = TestFile::new(extension);
+        fs::write(&file.path, content).unwrap();
+        file
+    }
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TestFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,40 @@
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipsoid {
+    pub a: f64,
+    pub e2: f64,
+}
+
+impl Ellipsoid {
+    pub fn new(a: f64, inverse_flattening: f64) -> Self {
+        // inverse flattening 0 stands for a sphere
+        let f = if inverse_flattening == 0. {
+            0.
+        } else {
+            1. / inverse_flattening
+        };
+        Ellipsoid {
+            a,
+            e2: 2. * f - f * f,
+        }
+    }
+
+    pub fn wgs84() -> Self {
+        Ellipsoid::new(6378137., 298.257223563)
+    }
+
+    pub fn grs80() -> Self {
+        Ellipsoid::new(6378137., 298.257222101)
+    }
+
+    pub fn bessel() -> Self {
+        Ellipsoid::new(6377397.155, 299.1528128)
+    }
+
+    pub fn airy() -> Self {
+        Ellipsoid::new(6377563.396, 299.3249646)
+    }
+
+    pub fn e(&self) -> f64 {
+        self.e2.sqrt()
+    }
+}
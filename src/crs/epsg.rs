@@ -0,0 +1,179 @@
+use super::{
+    ellipsoid::Ellipsoid,
+    projections::{
+        Geographic, Krovak, LambertAzimuthalEqualArea, LambertConformalConic, ObliqueStereographic,
+        Projection, TransverseMercator,
+    },
+    Crs,
+};
+
+// supported geographic CRS (WGS 84, ETRS89, NAD83, S-JTSK, OSGB36, Amersfoort)
+const GEOGRAPHIC_CODES: [u32; 6] = [4326, 4258, 4269, 4156, 4277, 4289];
+
+pub fn get_crs_from_epsg(code: u32) -> Result<Crs, String> {
+    let projection: Box<dyn Projection> = match code {
+        code if GEOGRAPHIC_CODES.contains(&code) => Box::new(Geographic),
+        // WGS 84 / UTM
+        32601..=32660 => Box::new(TransverseMercator::utm(
+            Ellipsoid::wgs84(),
+            code - 32600,
+            false,
+        )),
+        32701..=32760 => Box::new(TransverseMercator::utm(
+            Ellipsoid::wgs84(),
+            code - 32700,
+            true,
+        )),
+        // ETRS89 / UTM
+        25828..=25838 => Box::new(TransverseMercator::utm(
+            Ellipsoid::grs80(),
+            code - 25800,
+            false,
+        )),
+        // NAD83 / UTM
+        26901..=26923 => Box::new(TransverseMercator::utm(
+            Ellipsoid::grs80(),
+            code - 26900,
+            false,
+        )),
+        // ETRS89 / TM35FIN, SWEREF99 TM
+        3067 => Box::new(TransverseMercator::utm(Ellipsoid::grs80(), 35, false)),
+        3006 => Box::new(TransverseMercator::utm(Ellipsoid::grs80(), 33, false)),
+        // S-JTSK / Krovak (South West & East North)
+        2065 | 5513 | 5514 | 8352 | 8353 => Box::new(
+            Krovak::new(
+                Ellipsoid::bessel(),
+                49.5f64.to_radians(),
+                (24. + 50. / 60.0f64).to_radians(),
+                (30. + 17. / 60. + 17.30311 / 3600.0f64).to_radians(),
+                78.5f64.to_radians(),
+                0.9999,
+                (0., 0.),
+            )
+            .with_east_north(matches!(code, 5514 | 8353)),
+        ),
+        // OSGB36 / British National Grid
+        27700 => Box::new(TransverseMercator {
+            ellipsoid: Ellipsoid::airy(),
+            lat_origin: 49f64.to_radians(),
+            lon_origin: (-2f64).to_radians(),
+            scale_factor: 0.9996012717,
+            false_easting: 400000.,
+            false_northing: -100000.,
+        }),
+        // ETRF2000-PL / CS92
+        2180 => Box::new(TransverseMercator {
+            ellipsoid: Ellipsoid::grs80(),
+            lat_origin: 0.,
+            lon_origin: 19f64.to_radians(),
+            scale_factor: 0.9993,
+            false_easting: 500000.,
+            false_northing: -5300000.,
+        }),
+        // Slovenia 1996 / Slovene National Grid
+        3794 => Box::new(TransverseMercator {
+            ellipsoid: Ellipsoid::grs80(),
+            lat_origin: 0.,
+            lon_origin: 15f64.to_radians(),
+            scale_factor: 0.9999,
+            false_easting: 500000.,
+            false_northing: -5000000.,
+        }),
+        // HTRS96 / Croatia TM
+        3765 => Box::new(TransverseMercator {
+            ellipsoid: Ellipsoid::grs80(),
+            lat_origin: 0.,
+            lon_origin: 16.5f64.to_radians(),
+            scale_factor: 0.9999,
+            false_easting: 500000.,
+            false_northing: 0.,
+        }),
+        // RGF93 / Lambert-93
+        2154 => Box::new(LambertConformalConic::two_standard_parallels(
+            Ellipsoid::grs80(),
+            46.5f64.to_radians(),
+            3f64.to_radians(),
+            (49f64.to_radians(), 44f64.to_radians()),
+            700000.,
+            6600000.,
+        )),
+        // MGI / Austria Lambert
+        31287 => Box::new(LambertConformalConic::two_standard_parallels(
+            Ellipsoid::bessel(),
+            47.5f64.to_radians(),
+            (13. + 20. / 60.0f64).to_radians(),
+            (49f64.to_radians(), 46f64.to_radians()),
+            400000.,
+            400000.,
+        )),
+        // ETRS89 / LAEA Europe
+        3035 => Box::new(LambertAzimuthalEqualArea::new(
+            Ellipsoid::grs80(),
+            52f64.to_radians(),
+            10f64.to_radians(),
+            4321000.,
+            3210000.,
+        )),
+        // Amersfoort / RD New
+        28992 => Box::new(ObliqueStereographic::new(
+            Ellipsoid::bessel(),
+            (52. + 9. / 60. + 22.178 / 3600.0f64).to_radians(),
+            (5. + 23. / 60. + 15.5 / 3600.0f64).to_radians(),
+            0.9999079,
+            155000.,
+            463000.,
+        )),
+        _ => return Err(format!("Unsupported CRS EPSG:{}", code)),
+    };
+
+    Ok(Crs {
+        name: format!("EPSG:{}", code),
+        projection,
+        linear_unit: 1.,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // reference points from IOGP Guidance Note 7-2 & a Krüger series evaluation for UTM
+    fn assert_lat_lon(code: u32, (x, y): (f64, f64), (lat, lon): (f64, f64)) {
+        let crs = get_crs_from_epsg(code).unwrap();
+        let (crs_lat, crs_lon) = crs.to_lat_lon(x, y);
+        assert!(
+            (crs_lat - lat).abs() < 1e-6,
+            "EPSG:{} lat {}",
+            code,
+            crs_lat
+        );
+        assert!(
+            (crs_lon - lon).abs() < 1e-6,
+            "EPSG:{} lon {}",
+            code,
+            crs_lon
+        );
+    }
+
+    #[test]
+    fn krovak_reference_points() {
+        let lat_lon = (
+            50. + 12. / 60. + 32.442 / 3600.,
+            16. + 50. / 60. + 59.179 / 3600.,
+        );
+        assert_lat_lon(5513, (568991.00, 1050538.63), lat_lon);
+        assert_lat_lon(5514, (-568991.00, -1050538.63), lat_lon);
+    }
+
+    #[test]
+    fn utm_reference_points() {
+        assert_lat_lon(32634, (360199.756, 5395848.789), (48.7, 19.1));
+        assert_lat_lon(32734, (259583.222, 6245888.045), (-33.9, 18.4));
+        assert_lat_lon(32633, (500000., 0.), (0., 15.));
+    }
+
+    #[test]
+    fn transverse_mercator_reference_point() {
+        assert_lat_lon(27700, (577274.99, 69740.50), (50.5, 0.5));
+    }
+}
@@ -0,0 +1,49 @@
+// GeoTIFF GeoKeyDirectoryTag as stored in LAS (LASF_Projection 34735)
+
+use super::{epsg::get_crs_from_epsg, Crs};
+
+const MODEL_TYPE_KEY: u16 = 1024;
+const GEOGRAPHIC_TYPE_KEY: u16 = 2048;
+const PROJECTED_CS_TYPE_KEY: u16 = 3072;
+const PROJ_LINEAR_UNITS_KEY: u16 = 3076;
+
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const USER_DEFINED: u16 = 32767;
+
+pub fn get_crs_from_geo_keys(data: &[u8]) -> Result<Crs, String> {
+    let shorts = data
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect::<Vec<u16>>();
+
+    let key_count = *shorts.get(3).ok_or("Invalid GeoTIFF keys")? as usize;
+
+    // only the keys with the value stored directly in the directory are needed
+    let get_key = |key_id: u16| {
+        shorts[4..]
+            .chunks_exact(4)
+            .take(key_count)
+            .find(|key| key[0] == key_id && key[1] == 0)
+            .map(|key| key[3])
+    };
+
+    let code = if get_key(MODEL_TYPE_KEY) == Some(MODEL_TYPE_GEOGRAPHIC) {
+        get_key(GEOGRAPHIC_TYPE_KEY)
+    } else {
+        get_key(PROJECTED_CS_TYPE_KEY).or_else(|| get_key(GEOGRAPHIC_TYPE_KEY))
+    };
+
+    match code {
+        Some(USER_DEFINED) => Err("User defined GeoTIFF CRS is not supported".to_string()),
+        Some(code) => {
+            let mut crs = get_crs_from_epsg(code as u32)?;
+            crs.linear_unit = match get_key(PROJ_LINEAR_UNITS_KEY) {
+                Some(9002) => 0.3048,        // foot
+                Some(9003) => 1200. / 3937., // US survey foot
+                _ => crs.linear_unit,
+            };
+            Ok(crs)
+        }
+        None => Err("No CRS code in GeoTIFF keys".to_string()),
+    }
+}
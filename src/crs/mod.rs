@@ -0,0 +1,118 @@
+use ::las::{Header as LasHeader, Vlr};
+use log::info;
+
+use crate::{cli::input_params::centroid::Centroid, io::PointSource};
+
+use self::geotiff::get_crs_from_geo_keys;
+use self::projections::Projection;
+use self::wkt::get_crs_from_wkt;
+
+mod ellipsoid;
+mod epsg;
+mod geotiff;
+mod projections;
+mod wkt;
+
+/// Coordinate reference system of the input cloud
+pub struct Crs {
+    pub name: String,
+    pub projection: Box<dyn Projection>,
+    /// size of the linear unit in meters
+    pub linear_unit: f64,
+}
+
+impl Crs {
+    /// Coords in the CRS units to (lat, lon) in degrees, datum shift to WGS 84 is neglected
+    /// (up to few hundred meters - irrelevant for the position of the Sun)
+    pub fn to_lat_lon(&self, x: f64, y: f64) -> (f64, f64) {
+        let (lat, lon) = self
+            .projection
+            .to_geographic(x * self.linear_unit, y * self.linear_unit);
+        let lon = (lon.to_degrees() + 540.) % 360. - 180.;
+        (lat.to_degrees(), lon)
+    }
+}
+
+pub fn get_crs(las_header: &LasHeader) -> Result<Crs, String> {
+    let wkt = find_projection_vlr(las_header, 2112)
+        .map(|vlr| get_crs_from_wkt(&String::from_utf8_lossy(&vlr.data)));
+    let geo_keys =
+        find_projection_vlr(las_header, 34735).map(|vlr| get_crs_from_geo_keys(&vlr.data));
+
+    match (wkt, geo_keys) {
+        (Some(Ok(crs)), _) | (_, Some(Ok(crs))) => Ok(crs),
+        (Some(Err(err)), _) | (_, Some(Err(err))) => Err(err),
+        (None, None) => Err("Input file has no CRS".to_string()),
+    }
+}
+
+/// Centre of the cloud extent in WGS 84 derived from the CRS of the input
+pub fn get_centroid_from_crs(point_source: &dyn PointSource) -> Result<Centroid, String> {
+    let (crs, (x, y, z)) = get_crs_and_centre(point_source)?;
+
+    let (lat, lon) = crs.to_lat_lon(x, y);
+
+    if !(-90.0..=90.).contains(&lat) || lat.is_nan() || lon.is_nan() {
+        return Err(format!(
+            "Cloud centre is out of the valid area of the CRS {}",
+            crs.name
+        ));
+    }
+
+    let elevation = z * crs.linear_unit;
+
+    info!(
+        "Centroid derived from CRS {}: {:.5}, {:.5}, {:.1}",
+        crs.name, lat, lon, elevation
+    );
+
+    Ok(Centroid {
+        lat,
+        lon,
+        elevation,
+    })
+}
+
+/// Angle (degrees) from the true north to the grid north in the centre of the cloud, clockwise
+pub fn get_meridian_convergence(point_source: &dyn PointSource) -> Result<f64, String> {
+    let (crs, (x, y, _)) = get_crs_and_centre(point_source)?;
+
+    // direction of a short step along the grid north
+    let step = 100. / crs.linear_unit;
+    let (lat_1, lon_1) = crs.to_lat_lon(x, y);
+    let (lat_2, lon_2) = crs.to_lat_lon(x, y + step);
+
+    let d_lon = (lon_2 - lon_1 + 540.) % 360. - 180.;
+    let convergence = (d_lon * lat_1.to_radians().cos())
+        .atan2(lat_2 - lat_1)
+        .to_degrees();
+
+    if convergence.is_nan() {
+        Err(format!(
+            "Cloud centre is out of the valid area of the CRS {}",
+            crs.name
+        ))
+    } else {
+        Ok(convergence)
+    }
+}
+
+fn get_crs_and_centre(point_source: &dyn PointSource) -> Result<(Crs, (f64, f64, f64)), String> {
+    let las_header = point_source.las_header().ok_or("Input file has no CRS")?;
+    let extent = point_source
+        .extent()
+        .ok_or("Input file has no extent in the header")?;
+    let crs = get_crs(las_header)?;
+
+    let x = (extent.min.0 + extent.max.0) / 2.;
+    let y = (extent.min.1 + extent.max.1) / 2.;
+    let z = (extent.min.2 + extent.max.2) / 2.;
+
+    Ok((crs, (x, y, z)))
+}
+
+fn find_projection_vlr(las_header: &LasHeader, record_id: u16) -> Option<&Vlr> {
+    las_header
+        .all_vlrs()
+        .find(|vlr| vlr.user_id == "LASF_Projection" && vlr.record_id == record_id)
+}
@@ -0,0 +1,10 @@
+use super::Projection;
+
+/// Geographic coords stored as longitude (x) & latitude (y) in degrees
+pub struct Geographic;
+
+impl Projection for Geographic {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        (northing.to_radians(), easting.to_radians())
+    }
+}
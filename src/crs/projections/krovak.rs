@@ -0,0 +1,101 @@
+use std::f64::consts::PI;
+
+use crate::crs::ellipsoid::Ellipsoid;
+
+use super::{iterate_latitude, Projection};
+
+// EPSG 9819 & 1041 (East North variant with negated axes)
+pub struct Krovak {
+    ellipsoid: Ellipsoid,
+    east_north: bool,
+    lon_origin: f64,
+    azimuth: f64,
+    pseudo_standard_parallel: f64,
+    false_easting: f64,
+    false_northing: f64,
+    b: f64,
+    t0: f64,
+    n: f64,
+    r0: f64,
+}
+
+impl Krovak {
+    pub fn new(
+        ellipsoid: Ellipsoid,
+        lat_center: f64,
+        lon_origin: f64,
+        azimuth: f64,
+        pseudo_standard_parallel: f64,
+        scale_factor: f64,
+        (false_easting, false_northing): (f64, f64),
+    ) -> Self {
+        let Ellipsoid { a, e2 } = ellipsoid;
+        let e = ellipsoid.e();
+        let sin_lat_center = lat_center.sin();
+
+        let big_a = a * (1. - e2).sqrt() / (1. - e2 * sin_lat_center.powi(2));
+        let b = (1. + e2 * lat_center.cos().powi(4) / (1. - e2)).sqrt();
+        let gamma0 = (sin_lat_center / b).asin();
+        let t0 = (PI / 4. + gamma0 / 2.).tan()
+            * ((1. + e * sin_lat_center) / (1. - e * sin_lat_center)).powf(e * b / 2.)
+            / (PI / 4. + lat_center / 2.).tan().powf(b);
+        let n = pseudo_standard_parallel.sin();
+        let r0 = scale_factor * big_a / pseudo_standard_parallel.tan();
+
+        Krovak {
+            ellipsoid,
+            east_north: false,
+            lon_origin,
+            azimuth,
+            pseudo_standard_parallel,
+            false_easting,
+            false_northing,
+            b,
+            t0,
+            n,
+            r0,
+        }
+    }
+
+    /// Negated axes pointing east & north instead of the westing & southing
+    pub fn with_east_north(self, east_north: bool) -> Self {
+        Krovak { east_north, ..self }
+    }
+}
+
+impl Projection for Krovak {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let e = self.ellipsoid.e();
+        let b = self.b;
+
+        let (westing, southing) = if self.east_north {
+            (-easting, -northing)
+        } else {
+            (easting, northing)
+        };
+
+        let xp = southing - self.false_northing;
+        let yp = westing - self.false_easting;
+        let r = (xp * xp + yp * yp).sqrt();
+        let theta = yp.atan2(xp);
+        let d = theta / self.pseudo_standard_parallel.sin();
+        let t = 2.
+            * (((self.r0 / r).powf(1. / self.n)
+                * (PI / 4. + self.pseudo_standard_parallel / 2.).tan())
+            .atan()
+                - PI / 4.);
+        let u = (self.azimuth.cos() * t.sin() - self.azimuth.sin() * t.cos() * d.cos()).asin();
+        let v = (t.cos() * d.sin() / u.cos()).asin();
+
+        let lon = self.lon_origin - v / b;
+        let lat = iterate_latitude(u, e, |_, esin| {
+            2. * ((self.t0.powf(-1. / b)
+                * (u / 2. + PI / 4.).tan().powf(1. / b)
+                * ((1. + esin) / (1. - esin)).powf(e / 2.))
+            .atan()
+                - PI / 4.)
+        });
+
+        (lat, lon)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::crs::ellipsoid::Ellipsoid;
+
+use super::Projection;
+
+// EPSG 9820
+pub struct LambertAzimuthalEqualArea {
+    ellipsoid: Ellipsoid,
+    lat_origin: f64,
+    lon_origin: f64,
+    false_easting: f64,
+    false_northing: f64,
+    beta_origin: f64,
+    rq: f64,
+    d: f64,
+}
+
+impl LambertAzimuthalEqualArea {
+    pub fn new(
+        ellipsoid: Ellipsoid,
+        lat_origin: f64,
+        lon_origin: f64,
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        let Ellipsoid { a, e2 } = ellipsoid;
+        let e = ellipsoid.e();
+
+        let q = |lat: f64| {
+            let esin = e * lat.sin();
+            (1. - e2)
+                * (lat.sin() / (1. - esin * esin) - ((1. - esin) / (1. + esin)).ln() / (2. * e))
+        };
+        let q_pole = q(std::f64::consts::FRAC_PI_2);
+        let beta_origin = (q(lat_origin) / q_pole).asin();
+        let rq = a * (q_pole / 2.).sqrt();
+        let m_origin = lat_origin.cos() / (1. - e2 * lat_origin.sin().powi(2)).sqrt();
+        let d = a * m_origin / (rq * beta_origin.cos());
+
+        LambertAzimuthalEqualArea {
+            ellipsoid,
+            lat_origin,
+            lon_origin,
+            false_easting,
+            false_northing,
+            beta_origin,
+            rq,
+            d,
+        }
+    }
+}
+
+impl Projection for LambertAzimuthalEqualArea {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let e2 = self.ellipsoid.e2;
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        let d = self.d;
+        let dx = easting - self.false_easting;
+        let dy = northing - self.false_northing;
+
+        let rho = ((dx / d).powi(2) + (d * dy).powi(2)).sqrt();
+        if rho < 1e-10 {
+            return (self.lat_origin, self.lon_origin);
+        }
+
+        let c = 2. * (rho / (2. * self.rq)).asin();
+        let (sin_beta0, cos_beta0) = self.beta_origin.sin_cos();
+        let beta = (c.cos() * sin_beta0 + d * dy * c.sin() * cos_beta0 / rho).asin();
+
+        let lon = self.lon_origin
+            + (dx * c.sin())
+                .atan2(d * rho * cos_beta0 * c.cos() - d * d * dy * sin_beta0 * c.sin());
+        let lat = beta
+            + (e2 / 3. + 31. * e4 / 180. + 517. * e6 / 5040.) * (2. * beta).sin()
+            + (23. * e4 / 360. + 251. * e6 / 3780.) * (4. * beta).sin()
+            + (761. * e6 / 45360.) * (6. * beta).sin();
+
+        (lat, lon)
+    }
+}
@@ -0,0 +1,106 @@
+use std::f64::consts::PI;
+
+use crate::crs::ellipsoid::Ellipsoid;
+
+use super::{iterate_latitude, Projection};
+
+// EPSG 9801 & 9802
+pub struct LambertConformalConic {
+    ellipsoid: Ellipsoid,
+    n: f64,
+    af: f64,
+    r_origin: f64,
+    lon_origin: f64,
+    false_easting: f64,
+    false_northing: f64,
+}
+
+impl LambertConformalConic {
+    pub fn one_standard_parallel(
+        ellipsoid: Ellipsoid,
+        lat_origin: f64,
+        lon_origin: f64,
+        scale_factor: f64,
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        let e = ellipsoid.e();
+        let n = lat_origin.sin();
+        let t0 = get_t(lat_origin, e);
+        let af = ellipsoid.a * get_m(lat_origin, e) / (n * t0.powf(n)) * scale_factor;
+
+        LambertConformalConic {
+            ellipsoid,
+            n,
+            af,
+            r_origin: af * t0.powf(n),
+            lon_origin,
+            false_easting,
+            false_northing,
+        }
+    }
+
+    pub fn two_standard_parallels(
+        ellipsoid: Ellipsoid,
+        lat_origin: f64,
+        lon_origin: f64,
+        (lat_1, lat_2): (f64, f64),
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        let e = ellipsoid.e();
+        let (m1, m2) = (get_m(lat_1, e), get_m(lat_2, e));
+        let (t1, t2) = (get_t(lat_1, e), get_t(lat_2, e));
+
+        let n = if (lat_1 - lat_2).abs() < 1e-10 {
+            lat_1.sin()
+        } else {
+            (m1.ln() - m2.ln()) / (t1.ln() - t2.ln())
+        };
+        let af = ellipsoid.a * m1 / (n * t1.powf(n));
+
+        LambertConformalConic {
+            ellipsoid,
+            n,
+            af,
+            r_origin: af * get_t(lat_origin, e).powf(n),
+            lon_origin,
+            false_easting,
+            false_northing,
+        }
+    }
+}
+
+impl Projection for LambertConformalConic {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let e = self.ellipsoid.e();
+        let n = self.n;
+
+        let dx = easting - self.false_easting;
+        let dy = self.r_origin - (northing - self.false_northing);
+
+        let r = n.signum() * (dx * dx + dy * dy).sqrt();
+        let t = (r / self.af).powf(1. / n);
+        let theta = if n > 0. {
+            dx.atan2(dy)
+        } else {
+            (-dx).atan2(-dy)
+        };
+
+        let lat = iterate_latitude(PI / 2. - 2. * t.atan(), e, |_, esin| {
+            PI / 2. - 2. * (t * ((1. - esin) / (1. + esin)).powf(e / 2.)).atan()
+        });
+        let lon = theta / n + self.lon_origin;
+
+        (lat, lon)
+    }
+}
+
+fn get_m(lat: f64, e: f64) -> f64 {
+    lat.cos() / (1. - (e * lat.sin()).powi(2)).sqrt()
+}
+
+fn get_t(lat: f64, e: f64) -> f64 {
+    let esin = e * lat.sin();
+    (PI / 4. - lat / 2.).tan() / ((1. - esin) / (1. + esin)).powf(e / 2.)
+}
@@ -0,0 +1,32 @@
+mod geographic;
+mod krovak;
+mod lambert_azimuthal_equal_area;
+mod lambert_conformal_conic;
+mod oblique_stereographic;
+mod transverse_mercator;
+
+pub use self::geographic::Geographic;
+pub use self::krovak::Krovak;
+pub use self::lambert_azimuthal_equal_area::LambertAzimuthalEqualArea;
+pub use self::lambert_conformal_conic::LambertConformalConic;
+pub use self::oblique_stereographic::ObliqueStereographic;
+pub use self::transverse_mercator::TransverseMercator;
+
+// formulas from IOGP Guidance Note 7-2 (EPSG coordinate conversions)
+pub trait Projection: Send + Sync {
+    /// Projected coords in meters to geographic (lat, lon) in radians
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64);
+}
+
+// conformal latitude series iterated to the geodetic latitude
+fn iterate_latitude(mut lat: f64, e: f64, next: impl Fn(f64, f64) -> f64) -> f64 {
+    for _ in 0..15 {
+        let esin = e * lat.sin();
+        let next_lat = next(lat, esin);
+        if (next_lat - lat).abs() < 1e-12 {
+            return next_lat;
+        }
+        lat = next_lat;
+    }
+    lat
+}
@@ -0,0 +1,86 @@
+use std::f64::consts::PI;
+
+use crate::crs::ellipsoid::Ellipsoid;
+
+use super::{iterate_latitude, Projection};
+
+// EPSG 9809
+pub struct ObliqueStereographic {
+    ellipsoid: Ellipsoid,
+    lon_origin: f64,
+    scale_factor: f64,
+    false_easting: f64,
+    false_northing: f64,
+    r: f64,
+    n: f64,
+    c: f64,
+    chi0: f64,
+}
+
+impl ObliqueStereographic {
+    pub fn new(
+        ellipsoid: Ellipsoid,
+        lat_origin: f64,
+        lon_origin: f64,
+        scale_factor: f64,
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        let Ellipsoid { a, e2 } = ellipsoid;
+        let e = ellipsoid.e();
+        let sin_lat0 = lat_origin.sin();
+
+        let rho0 = a * (1. - e2) / (1. - e2 * sin_lat0.powi(2)).powf(1.5);
+        let nu0 = a / (1. - e2 * sin_lat0.powi(2)).sqrt();
+        let r = (rho0 * nu0).sqrt();
+        let n = (1. + e2 * lat_origin.cos().powi(4) / (1. - e2)).sqrt();
+
+        let s1 = (1. + sin_lat0) / (1. - sin_lat0);
+        let s2 = (1. - e * sin_lat0) / (1. + e * sin_lat0);
+        let w1 = (s1 * s2.powf(e)).powf(n);
+        let sin_chi0 = (w1 - 1.) / (w1 + 1.);
+        let c = (n + sin_lat0) * (1. - sin_chi0) / ((n - sin_lat0) * (1. + sin_chi0));
+        let w2 = c * w1;
+        let chi0 = ((w2 - 1.) / (w2 + 1.)).asin();
+
+        ObliqueStereographic {
+            ellipsoid,
+            lon_origin,
+            scale_factor,
+            false_easting,
+            false_northing,
+            r,
+            n,
+            c,
+            chi0,
+        }
+    }
+}
+
+impl Projection for ObliqueStereographic {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let e = self.ellipsoid.e();
+        let e2 = self.ellipsoid.e2;
+        let rk0 = self.r * self.scale_factor;
+        let dx = easting - self.false_easting;
+        let dy = northing - self.false_northing;
+
+        let g = 2. * rk0 * (PI / 4. - self.chi0 / 2.).tan();
+        let h = 4. * rk0 * self.chi0.tan() + g;
+        let i = (dx / (h + dy)).atan();
+        let j = (dx / (g - dy)).atan() - i;
+
+        let chi = self.chi0 + 2. * ((dy - dx * (j / 2.).tan()) / (2. * rk0)).atan();
+        let big_lambda = j + 2. * i + self.lon_origin;
+        let lon = (big_lambda - self.lon_origin) / self.n + self.lon_origin;
+
+        let psi = 0.5 * ((1. + chi.sin()) / (self.c * (1. - chi.sin()))).ln() / self.n;
+        let lat = iterate_latitude(2. * psi.exp().atan() - PI / 2., e, |lat, esin| {
+            let psi_i =
+                ((lat / 2. + PI / 4.).tan() * ((1. - esin) / (1. + esin)).powf(e / 2.)).ln();
+            lat - (psi_i - psi) * lat.cos() * (1. - esin * esin) / (1. - e2)
+        });
+
+        (lat, lon)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::crs::ellipsoid::Ellipsoid;
+
+use super::Projection;
+
+// EPSG 9807
+pub struct TransverseMercator {
+    pub ellipsoid: Ellipsoid,
+    pub lat_origin: f64,
+    pub lon_origin: f64,
+    pub scale_factor: f64,
+    pub false_easting: f64,
+    pub false_northing: f64,
+}
+
+impl TransverseMercator {
+    pub fn utm(ellipsoid: Ellipsoid, zone: u32, south: bool) -> Self {
+        TransverseMercator {
+            ellipsoid,
+            lat_origin: 0.,
+            lon_origin: (zone as f64 * 6. - 183.).to_radians(),
+            scale_factor: 0.9996,
+            false_easting: 500000.,
+            false_northing: if south { 10000000. } else { 0. },
+        }
+    }
+
+    fn meridian_arc(&self, lat: f64) -> f64 {
+        let Ellipsoid { a, e2 } = self.ellipsoid;
+        let e4 = e2 * e2;
+        let e6 = e4 * e2;
+        a * ((1. - e2 / 4. - 3. * e4 / 64. - 5. * e6 / 256.) * lat
+            - (3. * e2 / 8. + 3. * e4 / 32. + 45. * e6 / 1024.) * (2. * lat).sin()
+            + (15. * e4 / 256. + 45. * e6 / 1024.) * (4. * lat).sin()
+            - (35. * e6 / 3072.) * (6. * lat).sin())
+    }
+}
+
+impl Projection for TransverseMercator {
+    fn to_geographic(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let Ellipsoid { a, e2 } = self.ellipsoid;
+        let k0 = self.scale_factor;
+        let e4 = e2 * e2;
+        let e6 = e4 * e2;
+        let ep2 = e2 / (1. - e2);
+
+        let m1 = self.meridian_arc(self.lat_origin) + (northing - self.false_northing) / k0;
+        let mu1 = m1 / (a * (1. - e2 / 4. - 3. * e4 / 64. - 5. * e6 / 256.));
+        let e1 = (1. - (1. - e2).sqrt()) / (1. + (1. - e2).sqrt());
+
+        // footpoint latitude
+        let lat1 = mu1
+            + (3. * e1 / 2. - 27. * e1.powi(3) / 32.) * (2. * mu1).sin()
+            + (21. * e1.powi(2) / 16. - 55. * e1.powi(4) / 32.) * (4. * mu1).sin()
+            + (151. * e1.powi(3) / 96.) * (6. * mu1).sin()
+            + (1097. * e1.powi(4) / 512.) * (8. * mu1).sin();
+
+        let sin_lat1 = lat1.sin();
+        let nu1 = a / (1. - e2 * sin_lat1 * sin_lat1).sqrt();
+        let rho1 = a * (1. - e2) / (1. - e2 * sin_lat1 * sin_lat1).powf(1.5);
+        let t1 = lat1.tan().powi(2);
+        let c1 = ep2 * lat1.cos().powi(2);
+        let d = (easting - self.false_easting) / (nu1 * k0);
+
+        let lat = lat1
+            - (nu1 * lat1.tan() / rho1)
+                * (d.powi(2) / 2.
+                    - (5. + 3. * t1 + 10. * c1 - 4. * c1.powi(2) - 9. * ep2) * d.powi(4) / 24.
+                    + (61. + 90. * t1 + 298. * c1 + 45. * t1.powi(2)
+                        - 252. * ep2
+                        - 3. * c1.powi(2))
+                        * d.powi(6)
+                        / 720.);
+
+        let lon = self.lon_origin
+            + (d - (1. + 2. * t1 + c1) * d.powi(3) / 6.
+                + (5. - 2. * c1 + 28. * t1 - 3. * c1.powi(2) + 8. * ep2 + 24. * t1.powi(2))
+                    * d.powi(5)
+                    / 120.)
+                / lat1.cos();
+
+        (lat, lon)
+    }
+}
@@ -0,0 +1,495 @@
+// OGC WKT 1 (incl. ESRI flavour) & WKT 2 coordinate reference system definitions
+
+use std::{iter::Peekable, str::Chars};
+
+use super::{
+    ellipsoid::Ellipsoid,
+    epsg::get_crs_from_epsg,
+    projections::{
+        Geographic, Krovak, LambertAzimuthalEqualArea, LambertConformalConic, ObliqueStereographic,
+        Projection, TransverseMercator,
+    },
+    Crs,
+};
+
+const PROJECTED_KEYWORDS: [&str; 3] = ["PROJCS", "PROJCRS", "PROJECTEDCRS"];
+const GEOGRAPHIC_KEYWORDS: [&str; 4] = ["GEOGCS", "GEOGCRS", "GEODCRS", "GEOGRAPHICCRS"];
+const COMPOUND_KEYWORDS: [&str; 2] = ["COMPD_CS", "COMPOUNDCRS"];
+
+#[derive(Debug)]
+enum WktValue {
+    Node(WktNode),
+    Text(String),
+    Number(f64),
+}
+
+#[derive(Debug)]
+struct WktNode {
+    keyword: String,
+    values: Vec<WktValue>,
+}
+
+impl WktNode {
+    fn nodes(&self) -> impl Iterator<Item = &WktNode> {
+        self.values.iter().filter_map(|value| match value {
+            WktValue::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    fn node(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.nodes()
+            .find(|node| keywords.contains(&node.keyword.as_str()))
+    }
+
+    // depth first search
+    fn find_node(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.nodes().find_map(|node| {
+            if keywords.contains(&node.keyword.as_str()) {
+                Some(node)
+            } else {
+                node.find_node(keywords)
+            }
+        })
+    }
+
+    fn text(&self, idx: usize) -> Option<&str> {
+        match self.values.get(idx) {
+            Some(WktValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn number(&self, idx: usize) -> Option<f64> {
+        match self.values.get(idx) {
+            Some(WktValue::Number(number)) => Some(*number),
+            Some(WktValue::Text(text)) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn epsg_code(&self) -> Option<u32> {
+        let authority = self.node(&["AUTHORITY", "ID"])?;
+        if authority.text(0)?.eq_ignore_ascii_case("EPSG") {
+            authority.number(1).map(|code| code as u32)
+        } else {
+            None
+        }
+    }
+
+    // factor of the unit to meters or radians
+    fn unit_factor(&self, keywords: &[&str]) -> Option<f64> {
+        self.node(keywords).and_then(|unit| unit.number(1))
+    }
+}
+
+pub fn get_crs_from_wkt(wkt: &str) -> Result<Crs, String> {
+    let root = parse_wkt(wkt)?;
+
+    let crs = if COMPOUND_KEYWORDS.contains(&root.keyword.as_str()) {
+        root.nodes()
+            .find(|node| {
+                PROJECTED_KEYWORDS.contains(&node.keyword.as_str())
+                    || GEOGRAPHIC_KEYWORDS.contains(&node.keyword.as_str())
+            })
+            .ok_or("No horizontal CRS in compound WKT")?
+    } else {
+        &root
+    };
+
+    let name = crs.text(0).unwrap_or("unnamed").to_string();
+
+    if GEOGRAPHIC_KEYWORDS.contains(&crs.keyword.as_str()) {
+        Ok(Crs {
+            name,
+            projection: Box::new(Geographic),
+            linear_unit: 1.,
+        })
+    } else if PROJECTED_KEYWORDS.contains(&crs.keyword.as_str()) {
+        match crs.epsg_code().map(get_crs_from_epsg) {
+            Some(Ok(epsg_crs)) => Ok(Crs { name, ..epsg_crs }),
+            _ => get_projected_crs(crs, name),
+        }
+    } else {
+        Err(format!("Unsupported WKT CRS type {}", crs.keyword))
+    }
+}
+
+fn get_projected_crs(crs: &WktNode, name: String) -> Result<Crs, String> {
+    let linear_unit = crs
+        .unit_factor(&["UNIT", "LENGTHUNIT"])
+        .or_else(|| {
+            crs.nodes()
+                .filter(|node| node.keyword == "AXIS")
+                .find_map(|axis| axis.unit_factor(&["LENGTHUNIT", "UNIT"]))
+        })
+        .unwrap_or(1.);
+
+    let geographic_crs = crs.find_node(&GEOGRAPHIC_KEYWORDS);
+    let angular_unit = geographic_crs
+        .and_then(|geographic_crs| geographic_crs.unit_factor(&["UNIT", "ANGLEUNIT"]))
+        .unwrap_or_else(|| 1f64.to_radians());
+    let prime_meridian = crs
+        .find_node(&["PRIMEM", "PRIMEMERIDIAN"])
+        .and_then(|prime_meridian| prime_meridian.number(1))
+        .map(|lon| lon * angular_unit)
+        .unwrap_or(0.);
+
+    let ellipsoid = crs
+        .find_node(&["SPHEROID", "ELLIPSOID"])
+        .and_then(|ellipsoid| Some(Ellipsoid::new(ellipsoid.number(1)?, ellipsoid.number(2)?)))
+        .ok_or("Missing ellipsoid in WKT")?;
+
+    // WKT 2 keeps the method & parameters in the conversion node
+    let conversion = crs.node(&["CONVERSION"]).unwrap_or(crs);
+    let method = conversion
+        .node(&["PROJECTION", "METHOD"])
+        .and_then(|method| method.text(0))
+        .ok_or("Missing projection method in WKT")?;
+
+    let params = Params {
+        params: conversion
+            .nodes()
+            .filter(|node| node.keyword == "PARAMETER")
+            .filter_map(|param| {
+                let name = normalize_name(param.text(0)?);
+                let value = param.number(1)?;
+                let value = if is_angle_param(&name) {
+                    value
+                        * param
+                            .unit_factor(&["ANGLEUNIT", "UNIT"])
+                            .unwrap_or(angular_unit)
+                } else if is_length_param(&name) {
+                    value
+                        * param
+                            .unit_factor(&["LENGTHUNIT", "UNIT"])
+                            .unwrap_or(linear_unit)
+                } else {
+                    value
+                };
+                Some((name, value))
+            })
+            .collect(),
+    };
+
+    let false_origin = (
+        params.get_or(
+            &[
+                "falseeasting",
+                "eastingatfalseorigin",
+                "eastingatprojectioncentre",
+            ],
+            0.,
+        ),
+        params.get_or(
+            &[
+                "falsenorthing",
+                "northingatfalseorigin",
+                "northingatprojectioncentre",
+            ],
+            0.,
+        ),
+    );
+    let lat_origin = params.get_or(
+        &[
+            "latitudeoforigin",
+            "latitudeofnaturalorigin",
+            "latitudeoffalseorigin",
+            "latitudeofcenter",
+            "latitudeofprojectioncentre",
+        ],
+        0.,
+    );
+    let lon_origin = prime_meridian
+        + params.get_or(
+            &[
+                "centralmeridian",
+                "longitudeofnaturalorigin",
+                "longitudeoffalseorigin",
+                "longitudeofcenter",
+                "longitudeoforigin",
+            ],
+            0.,
+        );
+    let scale_factor = params.get_or(
+        &[
+            "scalefactor",
+            "scalefactoratnaturalorigin",
+            "scalefactoronpseudostandardparallel",
+        ],
+        1.,
+    );
+
+    let projection: Box<dyn Projection> = match normalize_name(method).as_str() {
+        "transversemercator" | "gausskruger" => Box::new(TransverseMercator {
+            ellipsoid,
+            lat_origin,
+            lon_origin,
+            scale_factor,
+            false_easting: false_origin.0,
+            false_northing: false_origin.1,
+        }),
+        "lambertconformalconic1sp" | "lambertconicconformal1sp" => {
+            Box::new(LambertConformalConic::one_standard_parallel(
+                ellipsoid,
+                lat_origin,
+                lon_origin,
+                scale_factor,
+                false_origin.0,
+                false_origin.1,
+            ))
+        }
+        "lambertconformalconic" | "lambertconformalconic2sp" | "lambertconicconformal2sp" => {
+            let standard_parallel_1 =
+                params.get(&["standardparallel1", "latitudeof1ststandardparallel"])?;
+            let standard_parallel_2 = params
+                .get(&["standardparallel2", "latitudeof2ndstandardparallel"])
+                .unwrap_or(standard_parallel_1);
+            Box::new(LambertConformalConic::two_standard_parallels(
+                ellipsoid,
+                lat_origin,
+                lon_origin,
+                (standard_parallel_1, standard_parallel_2),
+                false_origin.0,
+                false_origin.1,
+            ))
+        }
+        "krovak" | "krovakeastnorth" | "krovaknorthorientated" => Box::new(
+            Krovak::new(
+                ellipsoid,
+                lat_origin,
+                lon_origin,
+                params.get(&["azimuth", "colatitudeofconeaxis"])?,
+                params.get(&[
+                    "pseudostandardparallel1",
+                    "latitudeofpseudostandardparallel",
+                ])?,
+                scale_factor,
+                false_origin,
+            )
+            .with_east_north(normalize_name(method) != "krovak" || is_east_north(crs, &params)),
+        ),
+        "lambertazimuthalequalarea" => Box::new(LambertAzimuthalEqualArea::new(
+            ellipsoid,
+            lat_origin,
+            lon_origin,
+            false_origin.0,
+            false_origin.1,
+        )),
+        "obliquestereographic" | "doublestereographic" => Box::new(ObliqueStereographic::new(
+            ellipsoid,
+            lat_origin,
+            lon_origin,
+            scale_factor,
+            false_origin.0,
+            false_origin.1,
+        )),
+        _ => return Err(format!("Unsupported projection method \"{}\"", method)),
+    };
+
+    Ok(Crs {
+        name,
+        projection,
+        linear_unit,
+    })
+}
+
+// parameters in meters & radians
+struct Params {
+    params: Vec<(String, f64)>,
+}
+
+impl Params {
+    fn get(&self, names: &[&str]) -> Result<f64, String> {
+        names
+            .iter()
+            .find_map(|name| {
+                self.params
+                    .iter()
+                    .find(|(param_name, _)| param_name == name)
+                    .map(|(_, value)| *value)
+            })
+            .ok_or_else(|| format!("Missing projection parameter \"{}\" in WKT", names[0]))
+    }
+
+    fn get_or(&self, names: &[&str], default: f64) -> f64 {
+        self.get(names).unwrap_or(default)
+    }
+}
+
+// Krovak method with the axes pointing east & north (ESRI flips the x axis by a scale)
+fn is_east_north(crs: &WktNode, params: &Params) -> bool {
+    if let Ok(x_scale) = params.get(&["xscale"]) {
+        return x_scale < 0.;
+    }
+    crs.nodes()
+        .find(|node| node.keyword == "AXIS")
+        .and_then(|axis| axis.text(1))
+        .is_some_and(|direction| direction.eq_ignore_ascii_case("east"))
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|char| char.is_ascii_alphanumeric())
+        .map(|char| char.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_angle_param(name: &str) -> bool {
+    // scale factor on the pseudo standard parallel is not an angle
+    !name.starts_with("scalefactor")
+        && ["latitude", "longitude", "parallel", "meridian", "azimuth"]
+            .iter()
+            .any(|angle| name.contains(angle))
+}
+
+fn is_length_param(name: &str) -> bool {
+    name.contains("easting") || name.contains("northing")
+}
+
+fn parse_wkt(wkt: &str) -> Result<WktNode, String> {
+    let mut chars = wkt.trim_end_matches('\0').chars().peekable();
+    match parse_value(&mut chars)? {
+        WktValue::Node(node) => Ok(node),
+        _ => Err("Invalid WKT".to_string()),
+    }
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<WktValue, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('"') => {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    // doubled quote is an escaped quote
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        text.push('"');
+                    }
+                    Some('"') => break,
+                    Some(char) => text.push(char),
+                    None => return Err("Unterminated WKT string".to_string()),
+                }
+            }
+            Ok(WktValue::Text(text))
+        }
+        Some(char) if char.is_ascii_digit() || *char == '-' || *char == '+' || *char == '.' => {
+            let mut number = String::new();
+            while let Some(char) = chars.peek() {
+                if char.is_ascii_alphanumeric() || "+-.".contains(*char) {
+                    number.push(*char);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            number
+                .parse()
+                .map(WktValue::Number)
+                .map_err(|_| format!("Invalid WKT number \"{}\"", number))
+        }
+        Some(char) if char.is_ascii_alphabetic() => {
+            let mut keyword = String::new();
+            while let Some(char) = chars.peek() {
+                if char.is_ascii_alphanumeric() || *char == '_' {
+                    keyword.push(*char);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            skip_whitespace(chars);
+            if !matches!(chars.peek(), Some('[') | Some('(')) {
+                // enumerations like axis directions
+                return Ok(WktValue::Text(keyword));
+            }
+            chars.next();
+
+            let mut values = vec![];
+            loop {
+                values.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some(']') | Some(')') => break,
+                    _ => return Err(format!("Invalid WKT node {}", keyword)),
+                }
+            }
+            Ok(WktValue::Node(WktNode {
+                keyword: keyword.to_ascii_uppercase(),
+                values,
+            }))
+        }
+        _ => Err("Invalid WKT".to_string()),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|char| char.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KROVAK_LAT_LON: (f64, f64) = (50.20901167, 16.84977194);
+
+    fn assert_lat_lon(wkt: &str, (x, y): (f64, f64), (lat, lon): (f64, f64)) {
+        let crs = get_crs_from_wkt(wkt).unwrap();
+        let (crs_lat, crs_lon) = crs.to_lat_lon(x, y);
+        assert!((crs_lat - lat).abs() < 1e-6, "{} lat {}", crs.name, crs_lat);
+        assert!((crs_lon - lon).abs() < 1e-6, "{} lon {}", crs.name, crs_lon);
+    }
+
+    #[test]
+    fn krovak_east_north_from_axes() {
+        let wkt = r#"PROJCS["S-JTSK / Krovak East North",GEOGCS["S-JTSK",DATUM["System_Jednotne_Trigonometricke_Site_Katastralni",SPHEROID["Bessel 1841",6377397.155,299.1528128]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],PROJECTION["Krovak"],PARAMETER["latitude_of_center",49.5],PARAMETER["longitude_of_center",24.8333333333333],PARAMETER["azimuth",30.2881397527778],PARAMETER["pseudo_standard_parallel_1",78.5],PARAMETER["scale_factor",0.9999],PARAMETER["false_easting",0],PARAMETER["false_northing",0],UNIT["metre",1],AXIS["X",EAST],AXIS["Y",NORTH]]"#;
+        assert_lat_lon(wkt, (-568991.00, -1050538.63), KROVAK_LAT_LON);
+
+        let wkt = wkt.replace(
+            r#"AXIS["X",EAST],AXIS["Y",NORTH]"#,
+            r#"AXIS["X",SOUTH],AXIS["Y",WEST]"#,
+        );
+        assert_lat_lon(&wkt, (568991.00, 1050538.63), KROVAK_LAT_LON);
+    }
+
+    #[test]
+    fn krovak_east_north_from_esri_scale() {
+        let wkt = r#"PROJCS["S-JTSK_Krovak_East_North",GEOGCS["GCS_S_JTSK",DATUM["D_S_JTSK",SPHEROID["Bessel_1841",6377397.155,299.1528128]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Krovak"],PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],PARAMETER["Pseudo_Standard_Parallel_1",78.5],PARAMETER["Scale_Factor",0.9999],PARAMETER["Azimuth",30.28813975277778],PARAMETER["Longitude_Of_Center",24.83333333333333],PARAMETER["Latitude_Of_Center",49.5],PARAMETER["X_Scale",-1.0],PARAMETER["Y_Scale",1.0],PARAMETER["XY_Plane_Rotation",90.0],UNIT["Meter",1.0]]"#;
+        assert_lat_lon(wkt, (-568991.00, -1050538.63), KROVAK_LAT_LON);
+    }
+
+    #[test]
+    fn krovak_north_orientated_wkt2() {
+        let wkt = r#"PROJCRS["S-JTSK / Krovak East North",BASEGEOGCRS["S-JTSK",DATUM["System of the Unified Trigonometrical Cadastral Network",ELLIPSOID["Bessel 1841",6377397.155,299.1528128,LENGTHUNIT["metre",1]]],PRIMEM["Greenwich",0,ANGLEUNIT["degree",0.0174532925199433]]],CONVERSION["Krovak East North (Greenwich)",METHOD["Krovak (North Orientated)",ID["EPSG",1041]],PARAMETER["Latitude of projection centre",49.5,ANGLEUNIT["degree",0.0174532925199433]],PARAMETER["Longitude of origin",24.8333333333333,ANGLEUNIT["degree",0.0174532925199433]],PARAMETER["Co-latitude of cone axis",30.2881397527778,ANGLEUNIT["degree",0.0174532925199433]],PARAMETER["Latitude of pseudo standard parallel",78.5,ANGLEUNIT["degree",0.0174532925199433]],PARAMETER["Scale factor on pseudo standard parallel",0.9999,SCALEUNIT["unity",1]],PARAMETER["False easting",0,LENGTHUNIT["metre",1]],PARAMETER["False northing",0,LENGTHUNIT["metre",1]]],CS[Cartesian,2],AXIS["easting (X)",east,ORDER[1],LENGTHUNIT["metre",1]],AXIS["northing (Y)",north,ORDER[2],LENGTHUNIT["metre",1]]]"#;
+        assert_lat_lon(wkt, (-568991.00, -1050538.63), KROVAK_LAT_LON);
+    }
+
+    #[test]
+    fn lambert_conformal_conic_in_us_feet() {
+        let wkt = r#"PROJCS["NAD27 / Texas South Central",GEOGCS["NAD27",DATUM["North_American_Datum_1927",SPHEROID["Clarke 1866",6378206.4,294.978698213898]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],PROJECTION["Lambert_Conformal_Conic_2SP"],PARAMETER["standard_parallel_1",28.3833333333333],PARAMETER["standard_parallel_2",30.2833333333333],PARAMETER["latitude_of_origin",27.8333333333333],PARAMETER["central_meridian",-99],PARAMETER["false_easting",2000000],PARAMETER["false_northing",0],UNIT["US survey foot",0.304800609601219],AXIS["Easting",EAST],AXIS["Northing",NORTH]]"#;
+        assert_lat_lon(wkt, (2963503.91, 254759.80), (28.5, -96.));
+    }
+
+    #[test]
+    fn transverse_mercator_utm() {
+        let wkt = r#"PROJCS["WGS 84 / UTM zone 34N",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],PARAMETER["latitude_of_origin",0],PARAMETER["central_meridian",21],PARAMETER["scale_factor",0.9996],PARAMETER["false_easting",500000],PARAMETER["false_northing",0],UNIT["metre",1],AXIS["Easting",EAST],AXIS["Northing",NORTH]]"#;
+        assert_lat_lon(wkt, (360199.756, 5395848.789), (48.7, 19.1));
+    }
+
+    #[test]
+    fn projected_crs_with_authority_uses_epsg() {
+        let wkt = r#"PROJCS["S-JTSK / Krovak East North",GEOGCS["S-JTSK",SPHEROID["Bessel 1841",6377397.155,299.1528128]],PROJECTION["Krovak"],AUTHORITY["EPSG","5514"]]"#;
+        assert_lat_lon(wkt, (-568991.00, -1050538.63), KROVAK_LAT_LON);
+    }
+
+    #[test]
+    fn rejects_unsupported_projection() {
+        let wkt = r#"PROJCS["Mercator",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]]],PROJECTION["Mercator_1SP"],UNIT["metre",1]]"#;
+        assert!(get_crs_from_wkt(wkt).is_err());
+    }
+}
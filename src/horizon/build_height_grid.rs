@@ -0,0 +1,52 @@
+use std::error::Error;
+
+use crate::cli::InputParams;
+use crate::common::Extent;
+use crate::io::Reader;
+
+use super::Dem;
+
+// 400 MB of f32 elevations
+const MAX_HEIGHT_GRID_CELLS: usize = 100_000_000;
+
+/// Elevation raster of the cloud - the highest occluding point in each cell
+pub fn build_height_grid(
+    reader: &Reader,
+    extent: &Extent<f64>,
+    cell_size: f64,
+    input_params: &InputParams,
+) -> Result<Dem, Box<dyn Error>> {
+    let cols = ((extent.max.0 - extent.min.0) / cell_size).floor() as usize + 1;
+    let rows = ((extent.max.1 - extent.min.1) / cell_size).floor() as usize + 1;
+    if cols.saturating_mul(rows) > MAX_HEIGHT_GRID_CELLS {
+        return Err(format!(
+            "Height grid of {} x {} cells is too large, increase the cloud horizon cell size",
+            cols, rows
+        )
+        .into());
+    }
+    let origin = (extent.min.0, extent.min.1 + rows as f64 * cell_size);
+
+    let mut elevations = vec![f32::NAN; cols * rows];
+
+    for point in reader.to_point_source()? {
+        let classification = point.attributes.classification;
+        if input_params.is_dropped(classification) || !input_params.is_occluder(classification) {
+            continue;
+        }
+
+        let col = (((point.x - origin.0) / cell_size).floor() as usize).min(cols - 1);
+        let row = (((origin.1 - point.y) / cell_size).floor() as usize).min(rows - 1);
+        let elevation = &mut elevations[row * cols + col];
+        // NaN of the empty cell is replaced by max
+        *elevation = elevation.max(point.z as f32);
+    }
+
+    Ok(Dem {
+        cols,
+        rows,
+        origin,
+        cell_size: (cell_size, cell_size),
+        elevations,
+    })
+}
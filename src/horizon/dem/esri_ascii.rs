@@ -0,0 +1,120 @@
+// https://desktop.arcgis.com/en/arcmap/latest/manage-data/raster-and-images/esri-ascii-raster-format.htm
+
+use std::error::Error;
+use std::fs;
+
+use super::Dem;
+
+pub fn read_esri_ascii(path: &str) -> Result<Dem, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let mut tokens = content.split_whitespace().peekable();
+
+    let mut cols = None;
+    let mut rows = None;
+    let mut x_corner = None;
+    let mut y_corner = None;
+    let mut x_center = None;
+    let mut y_center = None;
+    let mut cell_size = None;
+    let mut nodata = None;
+
+    // header keys are followed by the values, the data starts with the first number
+    while let Some(key) = tokens.next_if(|token| token.parse::<f64>().is_err()) {
+        let value = tokens
+            .next()
+            .and_then(|value| value.parse::<f64>().ok())
+            .ok_or_else(|| format!("Missing value of \"{}\" in ESRI ASCII grid", key))?;
+
+        match key.to_lowercase().as_str() {
+            "ncols" => cols = Some(value as usize),
+            "nrows" => rows = Some(value as usize),
+            "xllcorner" => x_corner = Some(value),
+            "yllcorner" => y_corner = Some(value),
+            "xllcenter" => x_center = Some(value),
+            "yllcenter" => y_center = Some(value),
+            "cellsize" => cell_size = Some(value),
+            "nodata_value" => nodata = Some(value),
+            _ => return Err(format!("Unknown key \"{}\" in ESRI ASCII grid", key).into()),
+        }
+    }
+
+    let (cols, rows, cell_size) = match (cols, rows, cell_size) {
+        (Some(cols), Some(rows), Some(cell_size)) => (cols, rows, cell_size),
+        _ => return Err("ESRI ASCII grid requires ncols, nrows & cellsize".into()),
+    };
+
+    let x_min = match (x_corner, x_center) {
+        (Some(x_corner), _) => x_corner,
+        (None, Some(x_center)) => x_center - cell_size / 2.,
+        _ => return Err("ESRI ASCII grid requires xllcorner or xllcenter".into()),
+    };
+    let y_min = match (y_corner, y_center) {
+        (Some(y_corner), _) => y_corner,
+        (None, Some(y_center)) => y_center - cell_size / 2.,
+        _ => return Err("ESRI ASCII grid requires yllcorner or yllcenter".into()),
+    };
+
+    let elevations = tokens
+        .map(|token| match token.parse::<f64>() {
+            Ok(value) if Some(value) == nodata => Ok(f32::NAN),
+            Ok(value) => Ok(value as f32),
+            Err(_) => Err(format!(
+                "Cannot parse value \"{}\" of ESRI ASCII grid",
+                token
+            )),
+        })
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    Ok(Dem {
+        cols,
+        rows,
+        origin: (x_min, y_min + rows as f64 * cell_size),
+        cell_size: (cell_size, cell_size),
+        elevations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+
+    fn read(content: &str) -> Result<Dem, Box<dyn Error>> {
+        let file = TestFile::with_content("asc", content);
+        read_esri_ascii(file.path())
+    }
+
+    #[test]
+    fn reads_corner_header_and_nodata() {
+        let dem = read(
+            "ncols 3\nnrows 2\nxllcorner 1000\nyllcorner 2000\ncellsize 10\nNODATA_value -9999\n1 2 3\n4 -9999 6\n",
+        )
+        .unwrap();
+
+        assert_eq!((dem.cols, dem.rows), (3, 2));
+        assert_eq!(dem.origin, (1000., 2020.));
+        assert_eq!(dem.cell_size, (10., 10.));
+        assert_eq!(dem.elevations[0], 1.);
+        assert!(dem.elevations[4].is_nan());
+        // centre of the top left cell
+        assert_eq!(dem.get_elevation(1005., 2015.), Some(1.));
+        // cell without data is left out of the interpolation
+        assert_eq!(dem.get_elevation(1020., 2005.), Some(6.));
+        assert_eq!(dem.get_elevation(990., 2005.), None);
+    }
+
+    #[test]
+    fn reads_center_header() {
+        let dem = read("NCOLS 2\nNROWS 2\nXLLCENTER 1005\nYLLCENTER 2005\nCELLSIZE 10\n1 2\n3 4\n")
+            .unwrap();
+
+        assert_eq!(dem.origin, (1000., 2020.));
+        assert_eq!(dem.get_elevation(1010., 2010.), Some(2.5));
+    }
+
+    #[test]
+    fn rejects_incomplete_header() {
+        assert!(read("ncols 2\nnrows 2\ncellsize 10\n1 2\n3 4\n").is_err());
+        assert!(read("ncols 2\nfoo 2\n").is_err());
+    }
+}
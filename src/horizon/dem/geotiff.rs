@@ -0,0 +1,118 @@
+// http://docs.opengeospatial.org/is/19-008r4/19-008r4.html
+
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+use tiff::decoder::{Decoder, DecodingResult, Limits};
+use tiff::tags::Tag;
+
+use super::Dem;
+
+pub fn read_geotiff(path: &str) -> Result<Dem, Box<dyn Error>> {
+    let mut decoder =
+        Decoder::new(BufReader::new(File::open(path)?))?.with_limits(Limits::unlimited());
+    let (cols, rows) = decoder.dimensions()?;
+
+    let pixel_scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag);
+    let tie_point = decoder.get_tag_f64_vec(Tag::ModelTiepointTag);
+    let (cell_size, origin) = match (pixel_scale, tie_point) {
+        (Ok(pixel_scale), Ok(tie_point)) if pixel_scale.len() >= 2 && tie_point.len() >= 6 => {
+            // raster point (i, j) of the tie point is tied to the model point (x, y)
+            let (i, j, x, y) = (tie_point[0], tie_point[1], tie_point[3], tie_point[4]);
+            (
+                (pixel_scale[0], pixel_scale[1]),
+                (x - i * pixel_scale[0], y + j * pixel_scale[1]),
+            )
+        }
+        _ => {
+            return Err(format!(
+                "GeoTIFF \"{}\" has no georeferencing (ModelPixelScale & ModelTiepoint tags)",
+                path
+            )
+            .into())
+        }
+    };
+
+    let nodata = decoder
+        .get_tag_ascii_string(Tag::GdalNodata)
+        .ok()
+        .and_then(|nodata| {
+            nodata
+                .trim_matches(char::from(0))
+                .trim()
+                .parse::<f64>()
+                .ok()
+        })
+        // compared in the stored precision (e.g. -3.4028234663852886e+38 of f32 rasters)
+        .map(|nodata| nodata as f32);
+
+    let elevations: Vec<f64> = match decoder.read_image()? {
+        DecodingResult::U8(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::U16(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::U32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::U64(values) => values.into_iter().map(|value| value as f64).collect(),
+        DecodingResult::F32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::F64(values) => values,
+        DecodingResult::I8(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I16(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I64(values) => values.into_iter().map(|value| value as f64).collect(),
+    };
+
+    let elevations = elevations
+        .into_iter()
+        .map(|value| {
+            if Some(value as f32) == nodata {
+                f32::NAN
+            } else {
+                value as f32
+            }
+        })
+        .collect();
+
+    Ok(Dem {
+        cols: cols as usize,
+        rows: rows as usize,
+        origin,
+        cell_size,
+        elevations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    #[test]
+    fn reads_tie_point_scale_and_nodata() {
+        let test_file = TestFile::new("tif");
+        {
+            let mut file = File::create(test_file.path()).unwrap();
+            let mut encoder = TiffEncoder::new(&mut file).unwrap();
+            let mut image = encoder.new_image::<colortype::Gray32Float>(3, 2).unwrap();
+            image
+                .encoder()
+                .write_tag(Tag::ModelPixelScaleTag, &[10., 5., 0.][..])
+                .unwrap();
+            // raster point (1, 1) tied to the model point (1010, 2005)
+            image
+                .encoder()
+                .write_tag(Tag::ModelTiepointTag, &[1., 1., 0., 1010., 2005., 0.][..])
+                .unwrap();
+            image.encoder().write_tag(Tag::GdalNodata, "-9999").unwrap();
+            image.write_data(&[1., 2., 3., 4., -9999., 6.]).unwrap();
+        }
+
+        let dem = read_geotiff(test_file.path()).unwrap();
+
+        assert_eq!((dem.cols, dem.rows), (3, 2));
+        assert_eq!(dem.origin, (1000., 2010.));
+        assert_eq!(dem.cell_size, (10., 5.));
+        assert_eq!(dem.elevations[5], 6.);
+        assert!(dem.elevations[4].is_nan());
+        assert_eq!(dem.get_elevation(1005., 2007.5), Some(1.));
+    }
+}
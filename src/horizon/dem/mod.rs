@@ -0,0 +1,81 @@
+use std::error::Error;
+
+use crate::cli::input_params::file::{RasterFile, RasterFileType};
+
+use self::esri_ascii::read_esri_ascii;
+use self::geotiff::read_geotiff;
+
+mod esri_ascii;
+mod geotiff;
+
+/// Elevation raster, cells are stored by rows from the top (north) row, no data as NaN
+pub struct Dem {
+    pub cols: usize,
+    pub rows: usize,
+    /// top left corner of the top left cell
+    pub origin: (f64, f64),
+    pub cell_size: (f64, f64),
+    pub elevations: Vec<f32>,
+}
+
+impl Dem {
+    /// Elevation bilinearly interpolated between the cell centres, cells without data are left out
+    pub fn get_elevation(&self, x: f64, y: f64) -> Option<f64> {
+        let col = (x - self.origin.0) / self.cell_size.0 - 0.5;
+        let row = (self.origin.1 - y) / self.cell_size.1 - 0.5;
+
+        let max_col = (self.cols - 1) as f64;
+        let max_row = (self.rows - 1) as f64;
+        if !(-0.5..=max_col + 0.5).contains(&col) || !(-0.5..=max_row + 0.5).contains(&row) {
+            return None;
+        }
+
+        let (col, row) = (col.clamp(0., max_col), row.clamp(0., max_row));
+        let (col_0, row_0) = (col.floor() as usize, row.floor() as usize);
+        let (col_1, row_1) = (
+            (col_0 + 1).min(self.cols - 1),
+            (row_0 + 1).min(self.rows - 1),
+        );
+        let (dx, dy) = (col - col_0 as f64, row - row_0 as f64);
+
+        let corners = [
+            (col_0, row_0, (1. - dx) * (1. - dy)),
+            (col_1, row_0, dx * (1. - dy)),
+            (col_0, row_1, (1. - dx) * dy),
+            (col_1, row_1, dx * dy),
+        ];
+
+        let (elevation_sum, weight_sum) = corners.iter().fold(
+            (0., 0.),
+            |(elevation_sum, weight_sum), (col, row, weight)| {
+                let elevation = self.elevations[row * self.cols + col] as f64;
+                if elevation.is_nan() || *weight <= 0. {
+                    (elevation_sum, weight_sum)
+                } else {
+                    (elevation_sum + elevation * weight, weight_sum + weight)
+                }
+            },
+        );
+
+        if weight_sum > 0. {
+            Some(elevation_sum / weight_sum)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn read_dem(raster_file: &RasterFile) -> Result<Dem, Box<dyn Error>> {
+    let dem = match raster_file.file_type {
+        RasterFileType::GeoTiff => read_geotiff(&raster_file.path)?,
+        RasterFileType::EsriAscii => read_esri_ascii(&raster_file.path)?,
+    };
+
+    if dem.cols == 0 || dem.rows == 0 || dem.elevations.len() != dem.cols * dem.rows {
+        return Err(format!("Invalid dimensions of the DEM \"{}\"", raster_file.path).into());
+    }
+    if dem.cell_size.0 <= 0. || dem.cell_size.1 <= 0. {
+        return Err(format!("Invalid cell size of the DEM \"{}\"", raster_file.path).into());
+    }
+    Ok(dem)
+}
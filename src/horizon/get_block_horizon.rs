@@ -0,0 +1,43 @@
+use crate::cli::input_params::horizon::Horizon;
+use crate::cli::InputParams;
+use crate::voxel::Block;
+
+use super::{trace_horizon, Dem};
+
+/// Horizon of the far field of the block - the cloud outside of the block and its overlap,
+/// seen from the centre of the block at the median elevation of its points
+pub fn get_block_horizon(
+    height_grid: &Dem,
+    block: &Block,
+    input_params: &InputParams,
+) -> Option<Horizon> {
+    let mut elevations: Vec<f64> = block
+        .points
+        .iter()
+        .filter(|point| !point.overlap)
+        .map(|point| point.z + block.translation.z)
+        .collect();
+
+    if elevations.is_empty() {
+        return None;
+    }
+
+    let median_idx = elevations.len() / 2;
+    let (_, median_elevation, _) = elevations.select_nth_unstable_by(median_idx, f64::total_cmp);
+
+    let (min_x, min_y, max_x, max_y) = block.get_bbox();
+    let observer = (
+        (min_x + max_x) / 2.,
+        (min_y + max_y) / 2.,
+        *median_elevation,
+    );
+
+    Some(trace_horizon(
+        height_grid,
+        observer,
+        block.get_overlap_bbox(),
+        input_params.horizon_step,
+        input_params.horizon_distance,
+        input_params.meridian_convergence.unwrap_or(0.),
+    ))
+}
@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use crate::cli::input_params::horizon::Horizon;
+use crate::common::Extent;
+
+use super::{trace_horizon, Dem};
+
+/// Horizon seen from the centre of the cloud on the DEM surface,
+/// the terrain inside the cloud extent is left to the cloud itself
+pub fn get_dem_horizon(
+    dem: &Dem,
+    extent: &Extent<f64>,
+    angle_step: usize,
+    max_distance: Option<f64>,
+    meridian_convergence: f64,
+) -> Result<Horizon, Box<dyn Error>> {
+    let center_x = (extent.min.0 + extent.max.0) / 2.;
+    let center_y = (extent.min.1 + extent.max.1) / 2.;
+
+    let center_elevation = dem
+        .get_elevation(center_x, center_y)
+        .ok_or("Centre of the cloud is outside of the horizon DEM or has no data")?;
+
+    Ok(trace_horizon(
+        dem,
+        (center_x, center_y, center_elevation),
+        (extent.min.0, extent.min.1, extent.max.0, extent.max.1),
+        angle_step,
+        max_distance,
+        meridian_convergence,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // flat terrain with a 100 m high & 30 m wide ridge 1 km east of the cloud
+    fn get_dem() -> Dem {
+        let (cols, rows) = (300, 300);
+        let elevations = (0..rows)
+            .flat_map(|_| (0..cols).map(|col| if (249..=251).contains(&col) { 100. } else { 0. }))
+            .collect();
+        Dem {
+            cols,
+            rows,
+            origin: (0., 3000.),
+            cell_size: (10., 10.),
+            elevations,
+        }
+    }
+
+    #[test]
+    fn ridge_rises_the_eastern_horizon() {
+        let extent = Extent {
+            min: (1450., 1450., 0.),
+            max: (1550., 1550., 20.),
+        };
+        let horizon = get_dem_horizon(&get_dem(), &extent, 1, None, 0.).unwrap();
+
+        // ridge seen 5.7 degrees high
+        assert!(!horizon.is_visible(90f64.to_radians(), 5.5f64.to_radians()));
+        assert!(horizon.is_visible(90f64.to_radians(), 6f64.to_radians()));
+        assert!(horizon.is_visible(270f64.to_radians(), 0.1f64.to_radians()));
+
+        // the ridge is behind the search distance
+        let horizon = get_dem_horizon(&get_dem(), &extent, 1, Some(500.), 0.).unwrap();
+        assert!(horizon.is_visible(90f64.to_radians(), 0.1f64.to_radians()));
+    }
+
+    #[test]
+    fn rejects_cloud_outside_of_dem() {
+        let extent = Extent {
+            min: (5000., 5000., 0.),
+            max: (5100., 5100., 20.),
+        };
+        assert!(get_dem_horizon(&get_dem(), &extent, 1, None, 0.).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+mod build_height_grid;
+mod dem;
+mod get_block_horizon;
+mod get_dem_horizon;
+mod read_horizon_file;
+mod trace_horizon;
+
+pub use self::build_height_grid::build_height_grid;
+pub use self::dem::{read_dem, Dem};
+pub use self::get_block_horizon::get_block_horizon;
+pub use self::get_dem_horizon::get_dem_horizon;
+pub use self::read_horizon_file::read_horizon_file;
+use self::trace_horizon::trace_horizon;
@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fs;
+
+use crate::cli::input_params::horizon::Horizon;
+
+/// Reads (azimuth, height) pairs in degrees, separated by comma, semicolon or whitespace
+/// (e.g. GRASS r.horizon point output with -c -d flags), header & comment lines are skipped
+pub fn read_horizon_file(path: &str) -> Result<Horizon, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read horizon file \"{}\": {}", path, err))?;
+
+    let mut points = vec![];
+    let mut is_header = true;
+
+    for (line_idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values = line
+            .split(|char: char| char == ',' || char == ';' || char.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>();
+
+        match values {
+            Some(values) if values.len() == 2 => points.push((values[0], values[1])),
+            None if is_header => {}
+            _ => {
+                return Err(format!(
+                    "Invalid horizon line {} \"{}\" in \"{}\" (expected <AZIMUTH>,<HEIGHT>)",
+                    line_idx + 1,
+                    line,
+                    path
+                )
+                .into())
+            }
+        }
+        is_header = false;
+    }
+
+    Horizon::new(points).map_err(|err| format!("{} in \"{}\"", err, path).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+
+    fn read(content: &str) -> Result<Horizon, Box<dyn Error>> {
+        let file = TestFile::with_content("csv", content);
+        read_horizon_file(file.path())
+    }
+
+    #[test]
+    fn reads_separators_header_and_comments() {
+        let horizon =
+            read("azimuth,horizon_height\n# comment\n0,1.5\n90;2\n\n180 3\n270\t4\n360,1.5\n")
+                .unwrap();
+        assert_eq!(horizon.azimuths, vec![0., 90., 180., 270.]);
+        assert_eq!(horizon.horizon_height, vec![1.5, 2., 3., 4.]);
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(read("0,1\n90\n").is_err());
+        assert!(read("0,1\nnorth,2\n").is_err());
+        assert!(read("azimuth,height\n").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use rayon::prelude::*;
+
+use crate::cli::input_params::horizon::Horizon;
+
+use super::Dem;
+
+const EARTH_RADIUS: f64 = 6_371_000.;
+
+/// Horizon height (degrees) in the azimuths from the true north seen from the observer
+/// on the elevation raster. The area around the observer is skipped (left to the voxel shadowing).
+pub fn trace_horizon(
+    dem: &Dem,
+    observer: (f64, f64, f64),
+    skipped_area: (f64, f64, f64, f64),
+    angle_step: usize,
+    max_distance: Option<f64>,
+    meridian_convergence: f64,
+) -> Horizon {
+    let (observer_x, observer_y, observer_z) = observer;
+    let (min_x, min_y, max_x, max_y) = skipped_area;
+
+    let step = dem.cell_size.0.min(dem.cell_size.1);
+    // the raster diagonal limits the search if not specified
+    let max_distance = max_distance.unwrap_or_else(|| {
+        let width = dem.cols as f64 * dem.cell_size.0;
+        let height = dem.rows as f64 * dem.cell_size.1;
+        (width.powi(2) + height.powi(2)).sqrt()
+    });
+
+    let horizon_height: Vec<f64> = (0..360 / angle_step)
+        .into_par_iter()
+        .map(|angle_idx| {
+            let grid_azimuth =
+                ((angle_idx * angle_step) as f64 - meridian_convergence).to_radians();
+            let (dir_x, dir_y) = (grid_azimuth.sin(), grid_azimuth.cos());
+
+            // distance to the edge of the skipped area in the direction
+            let get_edge_distance = |coord: f64, min: f64, max: f64, dir: f64| {
+                if dir > f64::EPSILON {
+                    (max - coord) / dir
+                } else if dir < -f64::EPSILON {
+                    (min - coord) / dir
+                } else {
+                    f64::INFINITY
+                }
+            };
+            let start_distance = get_edge_distance(observer_x, min_x, max_x, dir_x)
+                .min(get_edge_distance(observer_y, min_y, max_y, dir_y));
+
+            let mut max_angle: f64 = 0.;
+            let mut distance = start_distance.max(step);
+            while distance <= max_distance {
+                let x = observer_x + dir_x * distance;
+                let y = observer_y + dir_y * distance;
+                if let Some(elevation) = dem.get_elevation(x, y) {
+                    // the terrain drops behind the horizon due to the Earth curvature
+                    let curvature_drop = distance.powi(2) / (2. * EARTH_RADIUS);
+                    let angle = (elevation - observer_z - curvature_drop).atan2(distance);
+                    max_angle = max_angle.max(angle);
+                }
+                distance += step;
+            }
+
+            max_angle.to_degrees()
+        })
+        .collect();
+
+    let azimuths = (0..360 / angle_step)
+        .map(|angle_idx| (angle_idx * angle_step) as f64)
+        .collect();
+    let is_flat = horizon_height.iter().all(|height| *height == 0.);
+
+    Horizon {
+        azimuths,
+        horizon_height,
+        is_flat,
+    }
+}
@@ -1,5 +1,5 @@
 mod read;
 mod write;
 
-pub use self::read::Reader;
+pub use self::read::{CloudPoint, PointAttributes, PointSource, Reader};
 pub use self::write::Writer;
\ No newline at end of file
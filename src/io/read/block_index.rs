@@ -0,0 +1,389 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cli::input_params::block_params::BlockParams;
+use crate::common::Extent;
+
+use super::{CloudPoint, PointAttributes, PointColor, PointSource};
+
+// memory used to buffer the points before they are appended to the block files, the block
+// buffers are at least the minimal size unless their sum exceeds the budget
+const SPILL_BUFFER_BUDGET: usize = 256 * 1024 * 1024;
+const MIN_BLOCK_BUFFER_SIZE: usize = 64 * 1024;
+const MAX_BLOCK_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+// every index (input cloud & occluder clouds) spills into its own directory
+static INDEX_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Points of the cloud bucketed (including overlaps) into temporary per-block files in a single pass
+pub struct BlockIndex {
+    dir: PathBuf,
+    y_blocks: usize,
+    point_counts: Vec<usize>,
+}
+
+impl BlockIndex {
+    pub fn new(
+        mut point_source: Box<dyn PointSource>,
+        extent: &Extent<f64>,
+        block_params: &BlockParams,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (x_blocks, y_blocks) = block_params.get_block_counts(extent);
+        let block_count = x_blocks * y_blocks;
+
+        let index_number = INDEX_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("pcsrt-{}-{}", process::id(), index_number));
+        fs::create_dir_all(&dir)?;
+
+        let mut block_index = BlockIndex {
+            dir,
+            y_blocks,
+            point_counts: vec![0; block_count],
+        };
+
+        let buffer_size =
+            (SPILL_BUFFER_BUDGET / block_count).clamp(MIN_BLOCK_BUFFER_SIZE, MAX_BLOCK_BUFFER_SIZE);
+        let mut buffers: Vec<Vec<u8>> = vec![vec![]; block_count];
+        let mut buffered_size = 0;
+
+        let size = block_params.size as f64;
+        let halo = block_params.get_halo();
+
+        for point in point_source.by_ref() {
+            let i_range = get_block_range(
+                point.x,
+                extent.min.0,
+                size,
+                (halo.left, halo.right),
+                x_blocks,
+            );
+            let j_range = get_block_range(
+                point.y,
+                extent.min.1,
+                size,
+                (halo.bottom, halo.top),
+                y_blocks,
+            );
+
+            for i in i_range.0..=i_range.1 {
+                for j in j_range.0..=j_range.1 {
+                    let block_idx = i * y_blocks + j;
+                    let buffer = &mut buffers[block_idx];
+                    let previous_len = buffer.len();
+                    write_point(buffer, &point);
+                    buffered_size += buffer.len() - previous_len;
+                    block_index.point_counts[block_idx] += 1;
+
+                    if buffer.len() > buffer_size {
+                        append_to_file(&block_index.get_block_path(block_idx), buffer)?;
+                        buffered_size -= buffer.len();
+                        buffer.clear();
+                    }
+                }
+            }
+
+            // the minimal buffers of many blocks would exceed the budget, the largest one is spilled
+            if buffered_size > SPILL_BUFFER_BUDGET {
+                if let Some((block_idx, buffer)) = buffers
+                    .iter_mut()
+                    .enumerate()
+                    .max_by_key(|(_, buffer)| buffer.len())
+                {
+                    append_to_file(&block_index.get_block_path(block_idx), buffer)?;
+                    buffered_size -= buffer.len();
+                    *buffer = vec![];
+                }
+            }
+        }
+
+        if let Some(err) = point_source.take_error() {
+            return Err(format!("Failed to read the cloud into blocks: {}", err).into());
+        }
+
+        for (block_idx, buffer) in buffers.iter().enumerate() {
+            append_to_file(&block_index.get_block_path(block_idx), buffer)?;
+        }
+
+        Ok(block_index)
+    }
+
+    pub fn to_point_source(
+        &self,
+        i: usize,
+        j: usize,
+    ) -> Result<Box<dyn PointSource>, Box<dyn Error>> {
+        let block_idx = i * self.y_blocks + j;
+        let file = File::open(self.get_block_path(block_idx))?;
+        Ok(Box::new(BlockFileReader {
+            reader: BufReader::new(file),
+            point_count: self.point_counts[block_idx],
+            remaining: self.point_counts[block_idx],
+            error: None,
+        }))
+    }
+
+    pub fn max_block_point_count(&self) -> usize {
+        self.point_counts.iter().copied().max().unwrap_or(0)
+    }
+
+    fn get_block_path(&self, block_idx: usize) -> PathBuf {
+        self.dir.join(format!("block_{}.bin", block_idx))
+    }
+}
+
+impl Drop for BlockIndex {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+pub struct BlockFileReader {
+    reader: BufReader<File>,
+    point_count: usize,
+    remaining: usize,
+    error: Option<std::io::Error>,
+}
+
+impl Iterator for BlockFileReader {
+    type Item = CloudPoint;
+    fn next(&mut self) -> Option<Self::Item> {
+        // the file ends after the counted points, anything failing before is an error
+        if self.remaining == 0 {
+            return None;
+        }
+        match read_point(&mut self.reader) {
+            Ok(point) => {
+                self.remaining -= 1;
+                Some(point)
+            }
+            Err(err) => {
+                self.remaining = 0;
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+impl PointSource for BlockFileReader {
+    fn extent(&self) -> Option<Extent<f64>> {
+        None
+    }
+    fn point_count(&self) -> Option<usize> {
+        Some(self.point_count)
+    }
+    fn take_error(&mut self) -> Option<Box<dyn Error>> {
+        self.error.take().map(|err| err.into())
+    }
+}
+
+// first & last block the coord falls into including the halos (halo bbox is closed),
+// outer blocks take all the points outside the extent
+fn get_block_range(
+    coord: f64,
+    min: f64,
+    size: f64,
+    (halo_before, halo_after): (f64, f64),
+    blocks: usize,
+) -> (usize, usize) {
+    let max_block = (blocks - 1) as f64;
+    let first = (((coord - halo_after - min) / size).ceil() - 1.).clamp(0., max_block);
+    let last = ((coord + halo_before - min) / size).floor().clamp(0., max_block);
+    (first as usize, last as usize)
+}
+
+fn append_to_file(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut file = BufWriter::new(file);
+    file.write_all(bytes)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn write_point(buffer: &mut Vec<u8>, point: &CloudPoint) {
+    let attributes = &point.attributes;
+
+    buffer.extend(point.x.to_le_bytes());
+    buffer.extend(point.y.to_le_bytes());
+    buffer.extend(point.z.to_le_bytes());
+
+    write_option(buffer, attributes.intensity.map(u16::to_le_bytes));
+    write_option(buffer, attributes.return_number.map(u8::to_le_bytes));
+    write_option(buffer, attributes.number_of_returns.map(u8::to_le_bytes));
+    write_option(buffer, attributes.classification.map(u8::to_le_bytes));
+    write_option(buffer, attributes.gps_time.map(f64::to_le_bytes));
+    write_option(
+        buffer,
+        attributes.color.map(|color| {
+            let mut bytes = [0; 6];
+            bytes[0..2].copy_from_slice(&color.red.to_le_bytes());
+            bytes[2..4].copy_from_slice(&color.green.to_le_bytes());
+            bytes[4..6].copy_from_slice(&color.blue.to_le_bytes());
+            bytes
+        }),
+    );
+    write_option(buffer, attributes.nir.map(u16::to_le_bytes));
+    write_option(buffer, attributes.scan_angle.map(f32::to_le_bytes));
+    write_option(buffer, attributes.user_data.map(u8::to_le_bytes));
+    write_option(buffer, attributes.point_source_id.map(u16::to_le_bytes));
+    write_option(buffer, attributes.scanner_channel.map(u8::to_le_bytes));
+
+    let flags = [
+        attributes.is_scan_direction_left_to_right,
+        attributes.is_edge_of_flight_line,
+        attributes.is_synthetic,
+        attributes.is_key_point,
+        attributes.is_withheld,
+        attributes.is_overlap,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0u8, |flags, (idx, flag)| flags | ((*flag as u8) << idx));
+    buffer.push(flags);
+
+    buffer.extend((attributes.extra_bytes.len() as u16).to_le_bytes());
+    buffer.extend(&attributes.extra_bytes);
+}
+
+fn write_option<const N: usize>(buffer: &mut Vec<u8>, value: Option<[u8; N]>) {
+    if let Some(value) = value {
+        buffer.push(1);
+        buffer.extend(value);
+    } else {
+        buffer.push(0);
+    }
+}
+
+fn read_point(reader: &mut impl Read) -> Result<CloudPoint, std::io::Error> {
+    let x = f64::from_le_bytes(read_bytes(reader)?);
+    let y = f64::from_le_bytes(read_bytes(reader)?);
+    let z = f64::from_le_bytes(read_bytes(reader)?);
+
+    let intensity = read_option(reader)?.map(u16::from_le_bytes);
+    let return_number = read_option(reader)?.map(u8::from_le_bytes);
+    let number_of_returns = read_option(reader)?.map(u8::from_le_bytes);
+    let classification = read_option(reader)?.map(u8::from_le_bytes);
+    let gps_time = read_option(reader)?.map(f64::from_le_bytes);
+    let color = read_option::<6>(reader)?.map(|bytes| PointColor {
+        red: u16::from_le_bytes([bytes[0], bytes[1]]),
+        green: u16::from_le_bytes([bytes[2], bytes[3]]),
+        blue: u16::from_le_bytes([bytes[4], bytes[5]]),
+    });
+    let nir = read_option(reader)?.map(u16::from_le_bytes);
+    let scan_angle = read_option(reader)?.map(f32::from_le_bytes);
+    let user_data = read_option(reader)?.map(u8::from_le_bytes);
+    let point_source_id = read_option(reader)?.map(u16::from_le_bytes);
+    let scanner_channel = read_option(reader)?.map(u8::from_le_bytes);
+
+    let [flags] = read_bytes::<1>(reader)?;
+    let flag = |idx: u8| flags & (1 << idx) != 0;
+
+    let extra_bytes_len = u16::from_le_bytes(read_bytes(reader)?) as usize;
+    let mut extra_bytes = vec![0; extra_bytes_len];
+    reader.read_exact(&mut extra_bytes)?;
+
+    Ok(CloudPoint {
+        x,
+        y,
+        z,
+        attributes: PointAttributes {
+            intensity,
+            return_number,
+            number_of_returns,
+            classification,
+            gps_time,
+            color,
+            nir,
+            scan_angle,
+            user_data,
+            point_source_id,
+            scanner_channel,
+            is_scan_direction_left_to_right: flag(0),
+            is_edge_of_flight_line: flag(1),
+            is_synthetic: flag(2),
+            is_key_point: flag(3),
+            is_withheld: flag(4),
+            is_overlap: flag(5),
+            extra_bytes,
+        },
+    })
+}
+
+fn read_option<const N: usize>(reader: &mut impl Read) -> Result<Option<[u8; N]>, std::io::Error> {
+    let [is_some] = read_bytes::<1>(reader)?;
+    match is_some {
+        0 => Ok(None),
+        1 => Ok(Some(read_bytes(reader)?)),
+        _ => Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "Corrupted block file",
+        )),
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], std::io::Error> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+
+    fn get_block_file_reader(bytes: &[u8], point_count: usize) -> BlockFileReader {
+        let test_file = TestFile::with_content("bin", bytes);
+        let file = File::open(test_file.path()).unwrap();
+        BlockFileReader {
+            reader: BufReader::new(file),
+            point_count,
+            remaining: point_count,
+            error: None,
+        }
+    }
+
+    fn get_point(x: f64) -> CloudPoint {
+        CloudPoint {
+            x,
+            y: 2.,
+            z: 3.,
+            attributes: PointAttributes {
+                classification: Some(6),
+                extra_bytes: vec![1, 2, 3],
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn reads_all_points_of_block_file() {
+        let mut bytes = vec![];
+        write_point(&mut bytes, &get_point(1.));
+        write_point(&mut bytes, &get_point(2.));
+
+        let mut reader = get_block_file_reader(&bytes, 2);
+        let points: Vec<CloudPoint> = reader.by_ref().collect();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1].x, 2.);
+        assert_eq!(points[1].attributes.classification, Some(6));
+        assert_eq!(points[1].attributes.extra_bytes, vec![1, 2, 3]);
+        assert!(reader.take_error().is_none());
+    }
+
+    #[test]
+    fn reports_truncated_block_file() {
+        let mut bytes = vec![];
+        write_point(&mut bytes, &get_point(1.));
+        write_point(&mut bytes, &get_point(2.));
+        bytes.pop();
+
+        let mut reader = get_block_file_reader(&bytes, 2);
+        assert_eq!(reader.by_ref().count(), 1);
+        assert!(reader.take_error().is_some());
+    }
+}
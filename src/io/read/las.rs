@@ -1,25 +1,168 @@
-use las::Point as LasPoint;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
 
-use crate::voxel::{GetCoords, IntoVoxelKey};
+use las::point::ScanDirection;
+use las::{Header as LasHeader, Point as LasPoint, Read, Reader as LasReader, Transform};
 
-impl IntoVoxelKey for LasPoint {
-    fn to_key(&self, voxel_size: f64) -> (i64, i64, i64) {
-        (
-            (self.x / voxel_size).round() as i64,
-            (self.y / voxel_size).round() as i64,
-            (self.z / voxel_size).round() as i64,
-        )
+use crate::common::Extent;
+
+use super::{CloudPoint, PointAttributes, PointColor, PointSource};
+
+// fraction of the scale step
+const GRID_TOLERANCE: f64 = 1e-6;
+
+pub struct LasPointReader {
+    reader: LasReader,
+    error: Option<las::Error>,
+}
+
+impl LasPointReader {
+    pub fn new(path: &str) -> Result<Self, Box<dyn Error>> {
+        let read = BufReader::new(File::open(path)?);
+        let reader = LasReader::new(read)?;
+        Ok(LasPointReader {
+            reader,
+            error: None,
+        })
+    }
+}
+
+impl Iterator for LasPointReader {
+    type Item = CloudPoint;
+    fn next(&mut self) -> Option<Self::Item> {
+        // the reading ends at the first failed point, the error is taken by the caller
+        if self.error.is_some() {
+            return None;
+        }
+        match self.reader.read()? {
+            Ok(point) => Some(CloudPoint::from(point)),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+impl PointSource for LasPointReader {
+    fn extent(&self) -> Option<Extent<f64>> {
+        let header = self.reader.header();
+        let bounds = header.bounds();
+        let transforms = header.transforms();
+        Some(Extent {
+            min: (
+                snap_to_grid(bounds.min.x, &transforms.x, f64::floor),
+                snap_to_grid(bounds.min.y, &transforms.y, f64::floor),
+                snap_to_grid(bounds.min.z, &transforms.z, f64::floor),
+            ),
+            max: (
+                snap_to_grid(bounds.max.x, &transforms.x, f64::ceil),
+                snap_to_grid(bounds.max.y, &transforms.y, f64::ceil),
+                snap_to_grid(bounds.max.z, &transforms.z, f64::ceil),
+            ),
+        })
+    }
+    fn point_count(&self) -> Option<usize> {
+        Some(self.reader.header().number_of_points() as usize)
     }
+    fn las_header(&self) -> Option<&LasHeader> {
+        Some(self.reader.header())
+    }
+    fn take_error(&mut self) -> Option<Box<dyn Error>> {
+        self.error.take().map(|err| err.into())
+    }
+}
+
+// header bounds may be written before the quantization, the points lie on the scale grid
+fn snap_to_grid(bound: f64, transform: &Transform, round: fn(f64) -> f64) -> f64 {
+    let steps = (bound - transform.offset) / transform.scale;
+    // bounds already on the grid are kept despite the float error
+    if (steps - steps.round()).abs() < GRID_TOLERANCE {
+        return bound;
+    }
+    transform.scale * round(steps) + transform.offset
 }
 
-impl GetCoords for LasPoint {
-    fn x(&self) -> f64 {
-        self.x
+impl From<LasPoint> for CloudPoint {
+    fn from(point: LasPoint) -> Self {
+        let attributes = PointAttributes {
+            intensity: Some(point.intensity),
+            return_number: Some(point.return_number),
+            number_of_returns: Some(point.number_of_returns),
+            classification: Some(u8::from(point.classification)),
+            gps_time: point.gps_time,
+            color: point.color.map(|color| PointColor {
+                red: color.red,
+                green: color.green,
+                blue: color.blue,
+            }),
+            nir: point.nir,
+            scan_angle: Some(point.scan_angle),
+            user_data: Some(point.user_data),
+            point_source_id: Some(point.point_source_id),
+            scanner_channel: Some(point.scanner_channel),
+            is_scan_direction_left_to_right: point.scan_direction == ScanDirection::LeftToRight,
+            is_edge_of_flight_line: point.is_edge_of_flight_line,
+            is_synthetic: point.is_synthetic,
+            is_key_point: point.is_key_point,
+            is_withheld: point.is_withheld,
+            is_overlap: point.is_overlap,
+            extra_bytes: point.extra_bytes,
+        };
+        CloudPoint {
+            x: point.x,
+            y: point.y,
+            z: point.z,
+            attributes,
+        }
     }
-    fn y(&self) -> f64 {
-        self.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+    use las::{Builder, Write, Writer};
+    use std::fs;
+
+    #[test]
+    fn snaps_header_bounds_to_point_grid() {
+        let transform = Transform {
+            scale: 0.001,
+            offset: 500000.,
+        };
+        assert_eq!(snap_to_grid(500039.9992, &transform, f64::ceil), 500040.);
+        assert_eq!(snap_to_grid(500000.0004, &transform, f64::floor), 500000.);
+        assert_eq!(snap_to_grid(500012.345, &transform, f64::ceil), 500012.345);
+        assert_eq!(snap_to_grid(500012.345, &transform, f64::floor), 500012.345);
     }
-    fn z(&self) -> f64 {
-        self.z
+
+    #[test]
+    fn reports_truncated_file() {
+        let file = TestFile::new("las");
+        {
+            let header = Builder::from((1, 2)).into_header().unwrap();
+            let mut writer = Writer::from_path(file.path(), header).unwrap();
+            for x in [1., 2.] {
+                writer
+                    .write(LasPoint {
+                        x,
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+        }
+
+        let mut reader = LasPointReader::new(file.path()).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.take_error().is_none());
+
+        let bytes = fs::read(file.path()).unwrap();
+        fs::write(file.path(), &bytes[..bytes.len() - 5]).unwrap();
+
+        let mut reader = LasPointReader::new(file.path()).unwrap();
+        assert_eq!(reader.by_ref().count(), 1);
+        assert!(reader.take_error().is_some());
     }
 }
@@ -1,16 +1,25 @@
-use ::las::Reader as LasReader;
-
-use std::fs::File;
-use std::io::BufReader;
+use std::error::Error;
 
+use crate::cli::input_params::block_params::BlockParams;
 use crate::cli::input_params::file::{File as InputFile, FileType};
+use crate::common::Extent;
+
+use self::block_index::BlockIndex;
+use self::las::LasPointReader;
+use self::ply::PlyPointReader;
 
+mod block_index;
 mod las;
 mod ply;
+mod structs;
+
+pub use self::structs::*;
 
 pub struct Reader {
     pub input_file: String,
     pub input_file_type: FileType,
+    block_index: Option<BlockIndex>,
+    crop_extent: Option<Extent<f64>>,
 }
 
 impl Reader {
@@ -18,10 +27,79 @@ impl Reader {
         Reader {
             input_file: input_file.path.to_owned(),
             input_file_type: input_file.file_type.clone(),
+            block_index: None,
+            crop_extent: None,
+        }
+    }
+    pub fn to_point_source(&self) -> Result<Box<dyn PointSource>, Box<dyn Error>> {
+        let point_source: Box<dyn PointSource> = match self.input_file_type {
+            FileType::Las | FileType::Laz => Box::new(LasPointReader::new(&self.input_file)?),
+            FileType::Ply => Box::new(PlyPointReader::new(&self.input_file)?),
+        };
+
+        match &self.crop_extent {
+            Some(crop_extent) => Ok(Box::new(CroppedPointSource {
+                point_source,
+                crop_extent: crop_extent.clone(),
+            })),
+            None => Ok(point_source),
+        }
+    }
+
+    /// Only the points inside the extent (in x & y) are read from the file
+    pub fn set_crop_extent(&mut self, crop_extent: Extent<f64>) {
+        self.crop_extent = Some(crop_extent);
+    }
+
+    /// Spills the points into per-block files so the blocks don't have to read the whole input
+    pub fn build_block_index(
+        &mut self,
+        extent: &Extent<f64>,
+        block_params: &BlockParams,
+    ) -> Result<(), Box<dyn Error>> {
+        let point_source = self.to_point_source()?;
+        self.block_index = Some(BlockIndex::new(point_source, extent, block_params)?);
+        Ok(())
+    }
+    pub fn to_block_point_source(
+        &self,
+        i: usize,
+        j: usize,
+    ) -> Result<Box<dyn PointSource>, Box<dyn Error>> {
+        match &self.block_index {
+            Some(block_index) => block_index.to_point_source(i, j),
+            None => self.to_point_source(),
         }
     }
-    pub fn to_point_reader(&self) -> LasReader {
-        let read = BufReader::new(File::open(&self.input_file).unwrap());
-        LasReader::new(read).unwrap()
+    pub fn max_block_point_count(&self) -> Option<usize> {
+        self.block_index
+            .as_ref()
+            .map(|block_index| block_index.max_block_point_count())
+    }
+}
+
+struct CroppedPointSource {
+    point_source: Box<dyn PointSource>,
+    crop_extent: Extent<f64>,
+}
+
+impl Iterator for CroppedPointSource {
+    type Item = CloudPoint;
+    fn next(&mut self) -> Option<Self::Item> {
+        let Extent { min, max } = &self.crop_extent;
+        self.point_source
+            .find(|point| (min.0..=max.0).contains(&point.x) && (min.1..=max.1).contains(&point.y))
+    }
+}
+
+impl PointSource for CroppedPointSource {
+    fn extent(&self) -> Option<Extent<f64>> {
+        None
+    }
+    fn point_count(&self) -> Option<usize> {
+        None
+    }
+    fn take_error(&mut self) -> Option<Box<dyn Error>> {
+        self.point_source.take_error()
     }
 }
@@ -1,23 +1,274 @@
+use ply_rs::parser::Parser;
+use ply_rs::ply::{DefaultElement, ElementDef, Encoding, Property};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind};
 
-// use ply_rs::{parser::Parser, ply::DefaultElement};
-// use std::fs::File;
-// use std::path::Path;
-
-// impl GetSerPoint for Point {
-//     fn ser_point(&self) -> SerPoint {
-//         SerPoint {
-//             x: self.x,
-//             y: self.y,
-//             z: self.z,
-//         }
-//     }
-// }
-
-// pub fn read<'a, P: AsRef<Path>>(path: P) {
-//     unimplemented!()
-    // let mut file = File::open(path).unwrap();
-    // let ply = Parser::<DefaultElement>::new().read_ply(&mut file).unwrap();
-    // let iter = ply.payload.into_iter();
-    // for (something, other_thing) in iter {
-    // }
-// }
+use log::warn;
+
+use crate::common::Extent;
+
+use super::{CloudPoint, PointAttributes, PointColor, PointSource};
+
+const POINT_ELEMENT_NAMES: [&str; 2] = ["vertex", "point"];
+
+pub struct PlyPointReader {
+    reader: BufReader<File>,
+    parser: Parser<DefaultElement>,
+    point_element: ElementDef,
+    encoding: Encoding,
+    remaining: usize,
+    skipped_points: usize,
+    error: Option<IoError>,
+    line: String,
+}
+
+impl PlyPointReader {
+    pub fn new(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let parser = Parser::<DefaultElement>::new();
+        let header = parser.read_header(&mut reader)?;
+
+        let point_element_name = header
+            .elements
+            .keys()
+            .find(|name| POINT_ELEMENT_NAMES.contains(&name.as_str()))
+            .cloned()
+            .ok_or_else(|| {
+                IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("No vertex element found in \"{}\"", path),
+                )
+            })?;
+
+        let point_element = header.elements[&point_element_name].clone();
+
+        for coord in ["x", "y", "z"] {
+            if !point_element.properties.contains_key(coord) {
+                return Err(Box::new(IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("Missing \"{}\" property in \"{}\"", coord, path),
+                )));
+            }
+        }
+
+        // elements stored before the points have to be read through
+        for (name, element) in &header.elements {
+            if *name == point_element_name {
+                break;
+            }
+            parser.read_payload_for_element(&mut reader, element, &header)?;
+        }
+
+        Ok(PlyPointReader {
+            reader,
+            parser,
+            remaining: point_element.count,
+            skipped_points: 0,
+            error: None,
+            point_element,
+            encoding: header.encoding,
+            line: String::new(),
+        })
+    }
+
+    fn read_element(&mut self) -> Result<DefaultElement, IoError> {
+        match self.encoding {
+            Encoding::Ascii => {
+                self.line.clear();
+                self.reader.read_line(&mut self.line)?;
+                self.parser
+                    .read_ascii_element(&self.line, &self.point_element)
+            }
+            Encoding::BinaryBigEndian => self
+                .parser
+                .read_big_endian_element(&mut self.reader, &self.point_element),
+            Encoding::BinaryLittleEndian => self
+                .parser
+                .read_little_endian_element(&mut self.reader, &self.point_element),
+        }
+    }
+}
+
+impl Iterator for PlyPointReader {
+    type Item = CloudPoint;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            self.remaining -= 1;
+            match self.read_element() {
+                Ok(element) => {
+                    let x = element.get("x").and_then(property_to_f64);
+                    let y = element.get("y").and_then(property_to_f64);
+                    let z = element.get("z").and_then(property_to_f64);
+
+                    if let (Some(x), Some(y), Some(z)) = (x, y, z) {
+                        let attributes = get_attributes(&element);
+                        return Some(CloudPoint {
+                            x,
+                            y,
+                            z,
+                            attributes,
+                        });
+                    }
+                    self.skipped_points += 1;
+                }
+                Err(err) => {
+                    // the rest of the payload cannot be trusted after a failed read
+                    self.remaining = 0;
+                    self.error = Some(err);
+                }
+            }
+        }
+        if self.skipped_points > 0 {
+            warn!(
+                "Skipped {} points of the input file without numeric coordinates.",
+                self.skipped_points
+            );
+            self.skipped_points = 0;
+        }
+        None
+    }
+}
+
+impl PointSource for PlyPointReader {
+    fn extent(&self) -> Option<Extent<f64>> {
+        None
+    }
+    fn point_count(&self) -> Option<usize> {
+        Some(self.point_element.count)
+    }
+    fn take_error(&mut self) -> Option<Box<dyn Error>> {
+        self.error.take().map(|err| err.into())
+    }
+}
+
+fn get_attributes(element: &DefaultElement) -> PointAttributes {
+    let get = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| element.get(*name))
+            .and_then(property_to_f64)
+    };
+
+    let get_color = || {
+        Some(PointColor {
+            red: color_to_u16(element.get("red")?)?,
+            green: color_to_u16(element.get("green")?)?,
+            blue: color_to_u16(element.get("blue")?)?,
+        })
+    };
+
+    PointAttributes {
+        intensity: get(&["intensity", "scalar_intensity"]).map(|val| val as u16),
+        return_number: get(&["return_number", "scalar_return_number"]).map(|val| val as u8),
+        number_of_returns: get(&["number_of_returns", "scalar_number_of_returns"])
+            .map(|val| val as u8),
+        classification: get(&["classification", "scalar_classification"]).map(|val| val as u8),
+        gps_time: get(&["gps_time", "scalar_gps_time"]),
+        color: get_color(),
+        ..Default::default()
+    }
+}
+
+// 8 bit colors are scaled to the 16 bit range used by las
+fn color_to_u16(property: &Property) -> Option<u16> {
+    match *property {
+        Property::UChar(val) => Some(u16::from(val) * 257),
+        _ => property_to_f64(property).map(|val| val as u16),
+    }
+}
+
+fn property_to_f64(property: &Property) -> Option<f64> {
+    match *property {
+        Property::Char(val) => Some(val as f64),
+        Property::UChar(val) => Some(val as f64),
+        Property::Short(val) => Some(val as f64),
+        Property::UShort(val) => Some(val as f64),
+        Property::Int(val) => Some(val as f64),
+        Property::UInt(val) => Some(val as f64),
+        Property::Float(val) => Some(val as f64),
+        Property::Double(val) => Some(val),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestFile;
+
+    fn get_binary_file(point_count: usize, truncate: bool) -> TestFile {
+        let mut bytes = format!(
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty double z\nproperty uchar scalar_classification\nend_header\n",
+            point_count
+        )
+        .into_bytes();
+        for idx in 0..point_count {
+            bytes.extend_from_slice(&(idx as f32).to_le_bytes());
+            bytes.extend_from_slice(&2f32.to_le_bytes());
+            bytes.extend_from_slice(&3.5f64.to_le_bytes());
+            bytes.push(6);
+        }
+        if truncate {
+            bytes.truncate(bytes.len() - 4);
+        }
+        TestFile::with_content("ply", bytes)
+    }
+
+    #[test]
+    fn reads_ascii_points_and_attributes() {
+        let file = TestFile::with_content(
+            "ply",
+            "ply\nformat ascii 1.0\nelement face 1\nproperty list uchar int vertex_indices\nelement vertex 2\nproperty double x\nproperty double y\nproperty double z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\nproperty ushort intensity\nproperty float gps_time\nend_header\n3 0 1 0\n1.5 2.5 3.5 255 0 1 100 10.5\n4 5 6 0 0 0 200 11\n",
+        );
+
+        let mut reader = PlyPointReader::new(file.path()).unwrap();
+        assert_eq!(reader.point_count(), Some(2));
+        assert!(reader.extent().is_none());
+
+        let points: Vec<CloudPoint> = reader.by_ref().collect();
+        assert!(reader.take_error().is_none());
+        assert_eq!(points.len(), 2);
+        assert_eq!((points[0].x, points[0].y, points[0].z), (1.5, 2.5, 3.5));
+
+        let attributes = &points[0].attributes;
+        let color = attributes.color.as_ref().unwrap();
+        // 8 bit colors are scaled to 16 bits
+        assert_eq!((color.red, color.green, color.blue), (65535, 0, 257));
+        assert_eq!(attributes.intensity, Some(100));
+        assert_eq!(attributes.gps_time, Some(10.5));
+        assert_eq!(attributes.classification, None);
+        assert_eq!(points[1].attributes.intensity, Some(200));
+    }
+
+    #[test]
+    fn reads_binary_points() {
+        let file = get_binary_file(3, false);
+        let mut reader = PlyPointReader::new(file.path()).unwrap();
+
+        let points: Vec<CloudPoint> = reader.by_ref().collect();
+        assert!(reader.take_error().is_none());
+        assert_eq!(points.len(), 3);
+        assert_eq!((points[2].x, points[2].y, points[2].z), (2., 2., 3.5));
+        assert_eq!(points[2].attributes.classification, Some(6));
+        assert!(points[2].attributes.color.is_none());
+    }
+
+    #[test]
+    fn reports_truncated_file() {
+        let file = get_binary_file(3, true);
+        let mut reader = PlyPointReader::new(file.path()).unwrap();
+
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.take_error().is_some());
+    }
+
+    #[test]
+    fn rejects_points_without_coordinates() {
+        let file = TestFile::with_content(
+            "ply",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty double x\nproperty double y\nend_header\n1 2\n",
+        );
+        assert!(PlyPointReader::new(file.path()).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::error::Error;
+
+use las::Header as LasHeader;
+
+use crate::common::Extent;
+use crate::voxel::{GetCoords, IntoVoxelKey};
+
+pub trait PointSource: Iterator<Item = CloudPoint> {
+    /// Extent stored in the file header, if the format provides one
+    fn extent(&self) -> Option<Extent<f64>>;
+    /// Point count stored in the file header, if the format provides one
+    fn point_count(&self) -> Option<usize>;
+    /// Original header used to carry the source layout and vlrs to las output
+    fn las_header(&self) -> Option<&LasHeader> {
+        None
+    }
+    /// Error that ended the reading before all the points were read
+    fn take_error(&mut self) -> Option<Box<dyn Error>> {
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CloudPoint {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub attributes: PointAttributes,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PointAttributes {
+    pub intensity: Option<u16>,
+    pub return_number: Option<u8>,
+    pub number_of_returns: Option<u8>,
+    pub classification: Option<u8>,
+    pub gps_time: Option<f64>,
+    pub color: Option<PointColor>,
+    pub nir: Option<u16>,
+    pub scan_angle: Option<f32>,
+    pub user_data: Option<u8>,
+    pub point_source_id: Option<u16>,
+    pub scanner_channel: Option<u8>,
+    pub is_scan_direction_left_to_right: bool,
+    pub is_edge_of_flight_line: bool,
+    pub is_synthetic: bool,
+    pub is_key_point: bool,
+    pub is_withheld: bool,
+    pub is_overlap: bool,
+    pub extra_bytes: Vec<u8>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PointColor {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+}
+
+impl IntoVoxelKey for CloudPoint {
+    fn to_key(&self, voxel_size: f64) -> (i64, i64, i64) {
+        (
+            (self.x / voxel_size).round() as i64,
+            (self.y / voxel_size).round() as i64,
+            (self.z / voxel_size).round() as i64,
+        )
+    }
+}
+
+impl GetCoords for CloudPoint {
+    fn x(&self) -> f64 {
+        self.x
+    }
+    fn y(&self) -> f64 {
+        self.y
+    }
+    fn z(&self) -> f64 {
+        self.z
+    }
+}
@@ -0,0 +1,276 @@
+// LAS 1.4 R15 - 2.6.3 Extra Bytes VLR
+
+use las::Vlr;
+use std::ops::Range;
+
+pub const EXTRA_BYTES_DESCRIPTOR_SIZE: usize = 192;
+
+/// Data types of the output fields (the input ones are copied as raw bytes)
+#[derive(Copy, Clone, Debug)]
+pub enum ExtraBytesDataType {
+    ULong = 5,
+    Float = 9,
+    Double = 10,
+}
+
+impl ExtraBytesDataType {
+    pub fn size(&self) -> usize {
+        match self {
+            ExtraBytesDataType::ULong | ExtraBytesDataType::Float => 4,
+            ExtraBytesDataType::Double => 8,
+        }
+    }
+
+    fn encode(&self, value: f64, bytes: &mut Vec<u8>) {
+        match self {
+            ExtraBytesDataType::ULong => bytes.extend((value.round() as u32).to_le_bytes()),
+            ExtraBytesDataType::Float => bytes.extend((value as f32).to_le_bytes()),
+            ExtraBytesDataType::Double => bytes.extend(value.to_le_bytes()),
+        }
+    }
+
+    // no_data, min and max are stored in 8 bytes as the 64 bit variant of the type
+    fn encode_any(&self, value: f64) -> [u8; 8] {
+        match self {
+            ExtraBytesDataType::ULong => (value.round() as u64).to_le_bytes(),
+            ExtraBytesDataType::Float | ExtraBytesDataType::Double => value.to_le_bytes(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtraBytesDescriptor {
+    pub name: String,
+    pub description: String,
+    pub data_type: ExtraBytesDataType,
+    pub no_data: Option<f64>,
+    pub scale: Option<f64>,
+    pub offset: Option<f64>,
+}
+
+impl ExtraBytesDescriptor {
+    pub fn new(name: &str, description: &str, data_type: ExtraBytesDataType) -> Self {
+        ExtraBytesDescriptor {
+            name: name.to_string(),
+            description: description.to_string(),
+            data_type,
+            no_data: None,
+            scale: None,
+            offset: None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut options = 0u8;
+        if self.no_data.is_some() {
+            options |= 1;
+        }
+        if self.scale.is_some() {
+            options |= 1 << 3;
+        }
+        if self.offset.is_some() {
+            options |= 1 << 4;
+        }
+
+        let mut bytes = Vec::with_capacity(EXTRA_BYTES_DESCRIPTOR_SIZE);
+        bytes.extend([0, 0]); // reserved
+        bytes.push(self.data_type as u8);
+        bytes.push(options);
+        bytes.extend(to_fixed_str::<32>(&self.name));
+        bytes.extend([0; 4]); // unused
+        bytes.extend(
+            self.no_data
+                .map(|no_data| self.data_type.encode_any(no_data))
+                .unwrap_or_default(),
+        );
+        bytes.extend([0; 16]); // deprecated
+        bytes.extend([0; 8]); // min
+        bytes.extend([0; 16]); // deprecated
+        bytes.extend([0; 8]); // max
+        bytes.extend([0; 16]); // deprecated
+        bytes.extend(self.scale.unwrap_or_default().to_le_bytes());
+        bytes.extend([0; 16]); // deprecated
+        bytes.extend(self.offset.unwrap_or_default().to_le_bytes());
+        bytes.extend([0; 16]); // deprecated
+        bytes.extend(to_fixed_str::<32>(&self.description));
+        bytes
+    }
+
+    /// Appends the value stored by the descriptor type (with scale & offset applied)
+    pub fn encode_value(&self, value: f64, bytes: &mut Vec<u8>) {
+        let value = (value - self.offset.unwrap_or(0.)) / self.scale.unwrap_or(1.);
+        self.data_type.encode(value, bytes);
+    }
+}
+
+pub fn get_extra_bytes_vlr(
+    input_descriptors: &[u8],
+    descriptors: &[ExtraBytesDescriptor],
+) -> Vlr {
+    let mut data = input_descriptors.to_vec();
+    for descriptor in descriptors {
+        data.extend(descriptor.to_bytes());
+    }
+    Vlr {
+        user_id: "LASF_Spec".to_string(),
+        record_id: 4,
+        description: "Extra Bytes Record".to_string(),
+        data,
+    }
+}
+
+pub fn is_extra_bytes_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id == "LASF_Spec" && vlr.record_id == 4
+}
+
+/// Documented extra bytes of the input file carried to the output
+#[derive(Default)]
+pub struct InputExtraBytes {
+    pub descriptors: Vec<u8>,
+    pub ranges: Vec<Range<usize>>,
+    pub size: usize,
+}
+
+impl InputExtraBytes {
+    /// Parses the input descriptors, fields named as the output fields are left out
+    pub fn new(
+        input_descriptors: &[u8],
+        input_size: usize,
+        descriptors: &[ExtraBytesDescriptor],
+    ) -> Option<Self> {
+        if !input_descriptors.len().is_multiple_of(EXTRA_BYTES_DESCRIPTOR_SIZE) {
+            return None;
+        }
+
+        let mut input_extra_bytes = InputExtraBytes::default();
+        let mut offset = 0;
+
+        for input_descriptor in input_descriptors.chunks(EXTRA_BYTES_DESCRIPTOR_SIZE) {
+            let data_type = input_descriptor[2] as usize;
+            let options = input_descriptor[3] as usize;
+            let size = match data_type {
+                0 => options, // undocumented bytes, options hold the size
+                1..=30 => {
+                    let base_size = [1, 1, 2, 2, 4, 4, 8, 8, 4, 8][(data_type - 1) % 10];
+                    let count = (data_type - 1) / 10 + 1; // deprecated 2 & 3 element arrays
+                    base_size * count
+                }
+                _ => return None,
+            };
+
+            let name = &input_descriptor[4..36];
+            let is_replaced = descriptors
+                .iter()
+                .any(|descriptor| to_fixed_str::<32>(&descriptor.name) == name);
+
+            if !is_replaced {
+                input_extra_bytes
+                    .descriptors
+                    .extend_from_slice(input_descriptor);
+                input_extra_bytes.ranges.push(offset..offset + size);
+                input_extra_bytes.size += size;
+            }
+            offset += size;
+        }
+
+        if offset == input_size {
+            Some(input_extra_bytes)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_bytes(&self, input_bytes: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size);
+        for range in &self.ranges {
+            match input_bytes.get(range.clone()) {
+                Some(input_bytes) => bytes.extend_from_slice(input_bytes),
+                None => bytes.extend(vec![0; range.len()]),
+            }
+        }
+        bytes
+    }
+}
+
+fn to_fixed_str<const N: usize>(str: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    for (byte, char) in bytes.iter_mut().zip(str.bytes().take(N - 1)) {
+        *byte = char;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_las_1_4_descriptor() {
+        let mut descriptor = ExtraBytesDescriptor::new(
+            "global_irradiance",
+            "Global irradiance [Wh/m2]",
+            ExtraBytesDataType::Double,
+        );
+        descriptor.no_data = Some(-1.);
+        descriptor.scale = Some(0.01);
+        descriptor.offset = Some(5.);
+
+        let bytes = descriptor.to_bytes();
+        assert_eq!(bytes.len(), EXTRA_BYTES_DESCRIPTOR_SIZE);
+        assert_eq!(bytes[..2], [0, 0]);
+        assert_eq!(bytes[2], 10);
+        // no_data, scale & offset bits
+        assert_eq!(bytes[3], 0b11001);
+
+        let mut name = [0; 32];
+        name[..17].copy_from_slice(b"global_irradiance");
+        assert_eq!(bytes[4..36], name);
+
+        assert_eq!(bytes[40..48], (-1f64).to_le_bytes());
+        assert_eq!(bytes[112..120], 0.01f64.to_le_bytes());
+        assert_eq!(bytes[136..144], 5f64.to_le_bytes());
+
+        let mut description = [0; 32];
+        description[..25].copy_from_slice(b"Global irradiance [Wh/m2]");
+        assert_eq!(bytes[160..192], description);
+    }
+
+    #[test]
+    fn writes_descriptor_without_options() {
+        let descriptor = ExtraBytesDescriptor::new(
+            "a_name_longer_than_thirty_two_characters",
+            "",
+            ExtraBytesDataType::ULong,
+        );
+
+        let bytes = descriptor.to_bytes();
+        assert_eq!(bytes.len(), EXTRA_BYTES_DESCRIPTOR_SIZE);
+        assert_eq!(bytes[2], 5);
+        assert_eq!(bytes[3], 0);
+        // the name is cut to keep the terminating null
+        assert_eq!(bytes[4..35], b"a_name_longer_than_thirty_two_c"[..]);
+        assert_eq!(bytes[35], 0);
+        assert!(bytes[36..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn parses_written_descriptors() {
+        let descriptors = [
+            ExtraBytesDescriptor::new("sun_hours", "", ExtraBytesDataType::Float),
+            ExtraBytesDescriptor::new("tree_height", "", ExtraBytesDataType::Double),
+        ];
+        let input_descriptors: Vec<u8> = descriptors
+            .iter()
+            .flat_map(|descriptor| descriptor.to_bytes())
+            .collect();
+
+        // the field named as an output field is replaced
+        let input_extra_bytes =
+            InputExtraBytes::new(&input_descriptors, 12, &descriptors[..1]).unwrap();
+        assert_eq!(input_extra_bytes.ranges, vec![4..12]);
+        assert_eq!(input_extra_bytes.size, 8);
+        assert_eq!(input_extra_bytes.descriptors, descriptors[1].to_bytes());
+
+        assert!(InputExtraBytes::new(&input_descriptors, 16, &[]).is_none());
+    }
+}
@@ -1,19 +1,35 @@
 use std::{fs::File, io::BufWriter};
 
 use crate::{
-    cli::input_params::file::{File as OutputFile, FileType},
+    cli::{input_params::file::FileType, InputParams},
     cloud_params::CloudParams,
-    voxel::{Irradiation, NormalVector, Point},
+    voxel::{Irradiation, NormalQuality, NormalVector, Point},
 };
 use las::{
-    point::Format, Builder, Color, Point as LasPoint, Transform, Vector, Write, Writer as LasWriter,
+    point::{Classification, Format, ScanDirection},
+    Builder, Color, Header as LasHeader, Point as LasPoint, Transform, Vector, Version, Vlr, Write,
+    Writer as LasWriter,
 };
+use log::warn;
 
+use super::extra_bytes::{
+    get_extra_bytes_vlr, is_extra_bytes_vlr, ExtraBytesDataType, ExtraBytesDescriptor,
+    InputExtraBytes,
+};
 use super::WriteOutput;
 use std::error::Error;
 
 pub struct LasFileWriter {
     writer: LasWriter<BufWriter<File>>,
+    extra_bytes_descriptors: Vec<ExtraBytesDescriptor>,
+    input_extra_bytes: InputExtraBytes,
+    output_reflected: bool,
+    output_sky_view_factor: bool,
+    output_normals: bool,
+    output_normal_quality: bool,
+    has_gps_time: bool,
+    has_color: bool,
+    has_nir: bool,
 }
 
 impl WriteOutput for LasFileWriter {
@@ -22,25 +38,89 @@ impl WriteOutput for LasFileWriter {
         point: Point,
         irradiation: &Irradiation,
         normal_vector: &NormalVector,
+        normal_quality: &NormalQuality,
+        sky_view_factor: f64,
     ) -> Result<(), Box<dyn Error>> {
-        let extra_bytes = vec![
+        let mut values = vec![
             irradiation.global_irradiance,
             irradiation.beam_component,
             irradiation.diffuse_component,
-            irradiation.sun_hours,
         ];
-        let normal_as_rgb = Color {
-            red: ((0.5 * normal_vector.x + 0.5) * 255.).round() as u16,
-            green: ((0.5 * normal_vector.y + 0.5) * 255.).round() as u16,
-            blue: ((0.5 * normal_vector.z + 0.5) * 255.).round() as u16,
+
+        if self.output_reflected {
+            values.push(irradiation.reflected_component);
+        }
+
+        values.push(irradiation.sun_hours);
+
+        if self.output_sky_view_factor {
+            values.push(sky_view_factor);
+        }
+
+        if self.output_normals {
+            values.extend([normal_vector.x, normal_vector.y, normal_vector.z]);
+        }
+
+        if self.output_normal_quality {
+            values.extend([normal_quality.point_count as f64, normal_quality.curvature]);
+        }
+
+        let color = if let Some(color) = point.attributes.color {
+            Color {
+                red: color.red,
+                green: color.green,
+                blue: color.blue,
+            }
+        } else if self.output_normals {
+            Color::default()
+        } else {
+            // normal is stored as color when the input has no color
+            Color {
+                red: ((0.5 * normal_vector.x + 0.5) * 255.).round() as u16,
+                green: ((0.5 * normal_vector.y + 0.5) * 255.).round() as u16,
+                blue: ((0.5 * normal_vector.z + 0.5) * 255.).round() as u16,
+            }
+        };
+
+        let attributes = point.attributes;
+
+        let mut extra_bytes = self.input_extra_bytes.get_bytes(&attributes.extra_bytes);
+        for (descriptor, value) in self.extra_bytes_descriptors.iter().zip(values) {
+            descriptor.encode_value(value, &mut extra_bytes);
+        }
+
+        let scan_direction = if attributes.is_scan_direction_left_to_right {
+            ScanDirection::LeftToRight
+        } else {
+            ScanDirection::RightToLeft
         };
-        let extra_bytes = to_byte_slice(&extra_bytes).to_vec();
         let point = LasPoint {
             x: point.x,
             y: point.y,
             z: point.z,
+            intensity: attributes.intensity.unwrap_or_default(),
+            return_number: attributes.return_number.unwrap_or_default(),
+            number_of_returns: attributes.number_of_returns.unwrap_or_default(),
+            scan_direction,
+            is_edge_of_flight_line: attributes.is_edge_of_flight_line,
+            classification: attributes
+                .classification
+                .and_then(|classification| Classification::new(classification).ok())
+                .unwrap_or_default(),
+            is_synthetic: attributes.is_synthetic,
+            is_key_point: attributes.is_key_point,
+            is_withheld: attributes.is_withheld,
+            is_overlap: attributes.is_overlap,
+            scanner_channel: attributes.scanner_channel.unwrap_or_default(),
+            scan_angle: attributes.scan_angle.unwrap_or_default(),
+            user_data: attributes.user_data.unwrap_or_default(),
+            point_source_id: attributes.point_source_id.unwrap_or_default(),
+            gps_time: self
+                .has_gps_time
+                .then(|| attributes.gps_time.unwrap_or_default()),
+            nir: self.has_nir.then(|| attributes.nir.unwrap_or_default()),
             extra_bytes,
-            color: Some(normal_as_rgb),
+            color: self.has_color.then_some(color),
             ..Default::default()
         };
 
@@ -51,86 +131,266 @@ impl WriteOutput for LasFileWriter {
 
 impl LasFileWriter {
     pub fn new(
-        output_file: &OutputFile,
+        input_params: &InputParams,
         cloud_params: &CloudParams,
+        input_header: Option<&LasHeader>,
     ) -> Result<Self, Box<dyn Error>> {
+        let output_file = &input_params.output_file;
         let file = File::create(&output_file.path)?;
         let file = BufWriter::new(file);
 
-        let mut builder = Builder::from((1, 2));
-        builder.point_format = Format::new(2).unwrap();
+        let mut builder = if let Some(input_header) = input_header {
+            get_builder_from_input_header(input_header, input_params.output_normals)
+        } else {
+            get_default_builder(cloud_params)
+        };
         builder.point_format.is_compressed = matches!(output_file.file_type, FileType::Laz);
-        builder.point_format.extra_bytes = 32;
 
-        let mut insolation_time_vlr = las::Vlr {
-            user_id: "LASF_Spec".to_string(),
-            record_id: 4,
-            description: "Extra Bytes Record".to_string(),
-            ..Default::default()
-        };
+        let extra_bytes_descriptors = get_extra_bytes_descriptors(input_params);
 
-        let fields = vec![
-            "irradiance",
-            "beam_component",
-            "diffuse_component",
-            "insolation_time",
-        ];
+        // documented extra bytes of the input are kept in front of the solar radiation fields
+        let input_descriptors = input_header
+            .and_then(|header| header.all_vlrs().find(|vlr| is_extra_bytes_vlr(vlr)))
+            .map(|vlr| vlr.data.clone())
+            .unwrap_or_default();
 
-        insolation_time_vlr.data = fields_to_vlr(&fields);
-        builder.evlrs.push(insolation_time_vlr);
+        let input_size = input_header
+            .map(|header| header.point_format().extra_bytes as usize)
+            .unwrap_or(0);
 
-        let min_x = cloud_params.extent.min.0.floor();
-        let min_y = cloud_params.extent.min.1.floor();
-        let min_z = cloud_params.extent.min.2.floor();
+        let input_extra_bytes =
+            InputExtraBytes::new(&input_descriptors, input_size, &extra_bytes_descriptors)
+                .unwrap_or_else(|| {
+                    warn!("Extra bytes of the input file are not documented, skipping them.");
+                    InputExtraBytes::default()
+                });
 
-        builder.transforms = Vector {
-            x: Transform {
-                offset: min_x,
-                scale: 0.001,
-            },
-            y: Transform {
-                offset: min_y,
-                scale: 0.001,
-            },
-            z: Transform {
-                offset: min_z,
-                scale: 0.001,
-            },
-        };
+        builder.point_format.extra_bytes = (input_extra_bytes.size
+            + extra_bytes_descriptors
+                .iter()
+                .map(|descriptor| descriptor.data_type.size())
+                .sum::<usize>()) as u16;
+
+        builder.vlrs.push(get_extra_bytes_vlr(
+            &input_extra_bytes.descriptors,
+            &extra_bytes_descriptors,
+        ));
 
-        let header = builder.into_header().unwrap();
+        let has_gps_time = builder.point_format.has_gps_time;
+        let has_color = builder.point_format.has_color;
+        let has_nir = builder.point_format.has_nir;
+
+        let header = builder.into_header()?;
 
         let writer = LasFileWriter {
-            writer: LasWriter::new(file, header).unwrap(),
+            writer: LasWriter::new(file, header)?,
+            extra_bytes_descriptors,
+            input_extra_bytes,
+            output_reflected: input_params.albedo.is_some(),
+            output_sky_view_factor: input_params.sky_view_factor,
+            output_normals: input_params.output_normals,
+            output_normal_quality: input_params.output_normal_quality,
+            has_gps_time,
+            has_color,
+            has_nir,
         };
 
         Ok(writer)
     }
 }
 
-fn fields_to_vlr(fields: &[&str]) -> Vec<u8> {
-    if fields.is_empty() {
-        return vec![];
+fn get_extra_bytes_descriptors(input_params: &InputParams) -> Vec<ExtraBytesDescriptor> {
+    let mut descriptors = vec![
+        ExtraBytesDescriptor::new(
+            "irradiance",
+            "Global irradiation [Wh/m2]",
+            ExtraBytesDataType::Double,
+        ),
+        ExtraBytesDescriptor::new(
+            "beam_component",
+            "Beam irradiation [Wh/m2]",
+            ExtraBytesDataType::Double,
+        ),
+        ExtraBytesDescriptor::new(
+            "diffuse_component",
+            "Diffuse irradiation [Wh/m2]",
+            ExtraBytesDataType::Double,
+        ),
+    ];
+
+    if input_params.albedo.is_some() {
+        descriptors.push(ExtraBytesDescriptor::new(
+            "reflected_component",
+            "Reflected irradiation [Wh/m2]",
+            ExtraBytesDataType::Double,
+        ));
     }
-    let mut vlr = vec![0, 0, 10, 0];
 
-    for (idx, field) in fields.iter().enumerate() {
-        let mut num_of_nulls = 192 - (field.len() + 1);
-        let is_last_row = idx + 1 == fields.len();
-        if is_last_row {
-            num_of_nulls -= 3;
-        } else {
-            num_of_nulls -= 1;
-        }
-        vlr.extend(field.as_bytes());
-        vlr.extend(vec![0; num_of_nulls]);
-        if !is_last_row {
-            vlr.extend(vec![10, 0]);
-        }
+    descriptors.push(ExtraBytesDescriptor::new(
+        "insolation_time",
+        "Insolation time [h]",
+        ExtraBytesDataType::Float,
+    ));
+
+    if input_params.sky_view_factor {
+        descriptors.push(ExtraBytesDescriptor::new(
+            "sky_view_factor",
+            "Visible fraction of the sky",
+            ExtraBytesDataType::Float,
+        ));
+    }
+
+    if input_params.output_normals {
+        descriptors.extend([
+            ExtraBytesDescriptor::new("nx", "Normal x", ExtraBytesDataType::Float),
+            ExtraBytesDescriptor::new("ny", "Normal y", ExtraBytesDataType::Float),
+            ExtraBytesDescriptor::new("nz", "Normal z", ExtraBytesDataType::Float),
+        ]);
+    }
+
+    if input_params.output_normal_quality {
+        descriptors.extend([
+            ExtraBytesDescriptor::new(
+                "normal_point_count",
+                "Points used for the normal",
+                ExtraBytesDataType::ULong,
+            ),
+            ExtraBytesDescriptor::new(
+                "normal_curvature",
+                "Surface variation of the points",
+                ExtraBytesDataType::Float,
+            ),
+        ]);
+    }
+
+    descriptors
+}
+
+fn get_default_builder(cloud_params: &CloudParams) -> Builder {
+    let mut builder = Builder::from((1, 4));
+    builder.point_format = Format::new(2).unwrap();
+
+    let min_x = cloud_params.extent.min.0.floor();
+    let min_y = cloud_params.extent.min.1.floor();
+    let min_z = cloud_params.extent.min.2.floor();
+
+    builder.transforms = Vector {
+        x: Transform {
+            offset: min_x,
+            scale: 0.001,
+        },
+        y: Transform {
+            offset: min_y,
+            scale: 0.001,
+        },
+        z: Transform {
+            offset: min_z,
+            scale: 0.001,
+        },
+    };
+    builder
+}
+
+fn get_builder_from_input_header(input_header: &LasHeader, output_normals: bool) -> Builder {
+    let mut builder = Builder::from(input_header.clone());
+
+    // waveform packets are not carried over
+    builder.point_format.has_waveform = false;
+    // normal is stored as color unless written to its own fields
+    if !output_normals {
+        builder.point_format.has_color = true;
     }
-    vlr
+    builder.version = Version::new(1, 4);
+
+    builder.vlrs.retain(is_carried_vlr);
+    builder.evlrs.retain(is_carried_vlr);
+    builder
+}
+
+// extra bytes are replaced by the solar radiation fields, laszip & waveform records are format specific
+fn is_carried_vlr(vlr: &Vlr) -> bool {
+    let is_waveform = vlr.user_id == "LASF_Spec"
+        && ((100..=354).contains(&vlr.record_id) || vlr.record_id == 65535);
+    let is_laszip = vlr.user_id == "laszip encoded";
+    !(is_extra_bytes_vlr(vlr) || is_waveform || is_laszip)
 }
 
-fn to_byte_slice(floats: &'_ [f64]) -> &'_ [u8] {
-    unsafe { std::slice::from_raw_parts(floats.as_ptr() as *const _, floats.len() * 8) }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::input_params::block_params::BlockParams;
+    use crate::common::{Extent, TestFile};
+    use crate::io::PointAttributes;
+    use clap::Parser;
+    use las::{Read, Reader};
+
+    #[test]
+    fn writes_normals_of_colorless_input() {
+        let input_file = TestFile::new("las");
+        let output_file = TestFile::new("las");
+
+        let mut builder = Builder::from((1, 4));
+        builder.point_format = Format::new(1).unwrap();
+        let input_header = builder.into_header().unwrap();
+
+        let input_params = InputParams::parse_from([
+            "pcsrt",
+            "-c",
+            "48.7,19.1,200",
+            "-t",
+            "2020-06-21T00:00:00Z,2020-06-22T00:00:00Z",
+            "-s",
+            "60",
+            "-l",
+            "3",
+            "--output-normals",
+            input_file.path(),
+            output_file.path(),
+        ]);
+        let cloud_params = CloudParams {
+            voxel_size: 1.,
+            average_points_in_voxel: 1.,
+            point_count: 1,
+            output_point_count: 1,
+            extent: Extent {
+                min: (0., 0., 0.),
+                max: (1., 1., 1.),
+            },
+            block_params: BlockParams {
+                size: 1000,
+                overlap: 0,
+                shadow_halo: None,
+            },
+        };
+
+        let mut writer =
+            LasFileWriter::new(&input_params, &cloud_params, Some(&input_header)).unwrap();
+        writer
+            .write_point(
+                Point {
+                    x: 0.5,
+                    y: 0.5,
+                    z: 0.5,
+                    overlap: false,
+                    attributes: PointAttributes::default(),
+                },
+                &Irradiation {
+                    global_irradiance: 1.,
+                    beam_component: 1.,
+                    diffuse_component: 0.,
+                    reflected_component: 0.,
+                    sun_hours: 1.,
+                },
+                &NormalVector::upright(),
+                &NormalQuality::default(),
+                1.,
+            )
+            .unwrap();
+        drop(writer);
+
+        let mut reader = Reader::from_path(output_file.path()).unwrap();
+        assert!(!reader.header().point_format().has_color);
+        let point = reader.read().unwrap().unwrap();
+        assert!(point.color.is_none());
+    }
 }
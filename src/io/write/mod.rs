@@ -1,36 +1,48 @@
+use ::las::Header as LasHeader;
 use std::error::Error;
 
 use crate::{
-    cli::input_params::file::{File, FileType},
+    cli::{input_params::file::FileType, InputParams},
     cloud_params::CloudParams,
-    voxel::{Irradiation, NormalVector, Point, TranslatePoint, Translation, Voxel, VoxelGrid},
+    voxel::{
+        Irradiation, NormalQuality, NormalVector, Point, TranslatePoint, Translation, Voxel,
+        VoxelGrid,
+    },
 };
 
 use self::{las::LasFileWriter, ply::PlyFileWriter};
 
+mod extra_bytes;
 mod las;
 mod ply;
 
-pub struct Writer {
+pub struct Writer<'a> {
     pub writer: Box<dyn WriteOutput>,
+    input_params: &'a InputParams,
 }
 
-impl Writer {
+impl<'a> Writer<'a> {
     pub fn new(
-        output_file: &File,
-        output_ply_ascii: bool,
+        input_params: &'a InputParams,
         cloud_params: &CloudParams,
+        input_header: Option<&LasHeader>,
     ) -> Result<Self, Box<dyn Error>> {
-        match output_file.file_type {
+        match input_params.output_file.file_type {
             FileType::Las | FileType::Laz => {
-                let writer = LasFileWriter::new(output_file, cloud_params)?;
+                let writer = LasFileWriter::new(input_params, cloud_params, input_header)?;
                 let writer = Box::from(writer);
-                Ok(Writer { writer })
+                Ok(Writer {
+                    writer,
+                    input_params,
+                })
             }
             FileType::Ply => {
-                let writer = PlyFileWriter::new(&output_file.path, output_ply_ascii, cloud_params)?; // todo
+                let writer = PlyFileWriter::new(input_params, cloud_params)?;
                 let writer = Box::from(writer);
-                Ok(Writer { writer })
+                Ok(Writer {
+                    writer,
+                    input_params,
+                })
             }
         }
     }
@@ -42,9 +54,21 @@ impl Writer {
         for (_, voxel) in voxel_grid.drain() {
             let irradiation = voxel.irradiation.read().unwrap();
 
-            for mut point in voxel.points.into_iter().filter(|point| !point.overlap) {
+            for mut point in voxel.points.into_iter().filter(|point| {
+                !point.overlap
+                    && self
+                        .input_params
+                        .is_output_point(point.attributes.classification)
+            }) {
                 point.translate_rev(translation);
-                self.write_point(point, &irradiation, &voxel.normal_vector)
+                self.writer
+                    .write_point(
+                        point,
+                        &irradiation,
+                        &voxel.normal_vector,
+                        &voxel.normal_quality,
+                        voxel.sky_view_factor,
+                    )
                     .unwrap();
             }
         }
@@ -52,14 +76,22 @@ impl Writer {
     }
 }
 
-impl WriteOutput for Writer {
+impl WriteOutput for Writer<'_> {
     fn write_point(
         &mut self,
         point: Point,
         irradiation: &Irradiation,
         normal_vector: &NormalVector,
+        normal_quality: &NormalQuality,
+        sky_view_factor: f64,
     ) -> Result<(), Box<dyn Error>> {
-        self.writer.write_point(point, irradiation, normal_vector)
+        self.writer.write_point(
+            point,
+            irradiation,
+            normal_vector,
+            normal_quality,
+            sky_view_factor,
+        )
     }
 }
 
@@ -69,5 +101,7 @@ pub trait WriteOutput {
         point: Point,
         irradiation: &Irradiation,
         normal_vector: &NormalVector,
+        normal_quality: &NormalQuality,
+        sky_view_factor: f64,
     ) -> Result<(), Box<dyn Error>>;
 }
@@ -6,8 +6,9 @@ use ply_rs::writer::Writer as PlyWriter;
 use std::error::Error;
 use std::{fs::File, io::BufWriter};
 
+use crate::cli::InputParams;
 use crate::cloud_params::CloudParams;
-use crate::voxel::{Irradiation, NormalVector, Point};
+use crate::voxel::{Irradiation, NormalQuality, NormalVector, Point};
 
 use super::WriteOutput;
 
@@ -16,6 +17,10 @@ pub struct PlyFileWriter {
     file: BufWriter<File>,
     point_element: ElementDef,
     ascii: bool,
+    output_reflected: bool,
+    output_sky_view_factor: bool,
+    output_normals: bool,
+    output_normal_quality: bool,
 }
 
 impl WriteOutput for PlyFileWriter {
@@ -23,7 +28,9 @@ impl WriteOutput for PlyFileWriter {
         &mut self,
         point: Point,
         irradiation: &Irradiation,
-        _normal_vector: &NormalVector,
+        normal_vector: &NormalVector,
+        normal_quality: &NormalQuality,
+        sky_view_factor: f64,
     ) -> Result<(), Box<dyn Error>> {
         let mut ply_point = DefaultElement::new();
 
@@ -42,10 +49,37 @@ impl WriteOutput for PlyFileWriter {
             "diffuse_component".to_string(),
             Property::Double(irradiation.diffuse_component),
         );
+        if self.output_reflected {
+            ply_point.insert(
+                "reflected_component".to_string(),
+                Property::Double(irradiation.reflected_component),
+            );
+        }
         ply_point.insert(
             "insolation_time".to_string(),
             Property::UInt(irradiation.sun_hours as u32),
         );
+        if self.output_sky_view_factor {
+            ply_point.insert(
+                "sky_view_factor".to_string(),
+                Property::Float(sky_view_factor as f32),
+            );
+        }
+        if self.output_normals {
+            ply_point.insert("nx".to_string(), Property::Float(normal_vector.x as f32));
+            ply_point.insert("ny".to_string(), Property::Float(normal_vector.y as f32));
+            ply_point.insert("nz".to_string(), Property::Float(normal_vector.z as f32));
+        }
+        if self.output_normal_quality {
+            ply_point.insert(
+                "normal_point_count".to_string(),
+                Property::UInt(normal_quality.point_count as u32),
+            );
+            ply_point.insert(
+                "normal_curvature".to_string(),
+                Property::Float(normal_quality.curvature as f32),
+            );
+        }
         if self.ascii {
             self.writer
                 .write_ascii_element(&mut self.file, &ply_point, &self.point_element)?;
@@ -63,11 +97,11 @@ impl WriteOutput for PlyFileWriter {
 
 impl PlyFileWriter {
     pub fn new(
-        path: &str,
-        ascii: bool,
+        input_params: &InputParams,
         cloud_params: &CloudParams,
     ) -> Result<Self, Box<dyn Error>> {
-        let file = File::create(path)?;
+        let ascii = input_params.output_ply_ascii;
+        let file = File::create(&input_params.output_file.path)?;
         let mut file = BufWriter::new(file);
         let writer: PlyWriter<DefaultElement> = PlyWriter::new();
         // crete a ply object
@@ -85,20 +119,44 @@ impl PlyFileWriter {
         let point_header_name = "point".to_string();
         let mut point_element = ElementDef {
             name: point_header_name,
-            count: cloud_params.point_count,
+            count: cloud_params.output_point_count,
             properties: KeyMap::new(),
         };
 
-        let property_names = [
+        let mut property_names = vec![
             ("x", ScalarType::Double),
             ("y", ScalarType::Double),
             ("z", ScalarType::Double),
             ("irradiance", ScalarType::Double),
             ("beam_component", ScalarType::Double),
             ("diffuse_component", ScalarType::Double),
-            ("insolation_time", ScalarType::UInt),
         ];
 
+        if input_params.albedo.is_some() {
+            property_names.push(("reflected_component", ScalarType::Double));
+        }
+
+        property_names.push(("insolation_time", ScalarType::UInt));
+
+        if input_params.sky_view_factor {
+            property_names.push(("sky_view_factor", ScalarType::Float));
+        }
+
+        if input_params.output_normals {
+            property_names.extend([
+                ("nx", ScalarType::Float),
+                ("ny", ScalarType::Float),
+                ("nz", ScalarType::Float),
+            ]);
+        }
+
+        if input_params.output_normal_quality {
+            property_names.extend([
+                ("normal_point_count", ScalarType::UInt),
+                ("normal_curvature", ScalarType::Float),
+            ]);
+        }
+
         for (property_name, scalar_type) in property_names.iter() {
             let property = PropertyDef::new(
                 property_name.to_string(),
@@ -115,6 +173,10 @@ impl PlyFileWriter {
             point_element,
             writer,
             ascii,
+            output_reflected: input_params.albedo.is_some(),
+            output_sky_view_factor: input_params.sky_view_factor,
+            output_normals: input_params.output_normals,
+            output_normal_quality: input_params.output_normal_quality,
         };
         Ok(ply_writer)
     }
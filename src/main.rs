@@ -8,6 +8,8 @@ use self::pcsrt::pcsrt;
 mod cli;
 mod cloud_params;
 mod common;
+mod crs;
+mod horizon;
 mod io;
 mod pcsrt;
 mod radiation;
@@ -1,22 +1,167 @@
 use std::error::Error;
+use std::mem::size_of;
+use std::sync::{mpsc, Mutex};
+use std::thread;
 
+use chrono::Datelike;
 use clap::Parser;
 use log::{info, warn};
 
 use crate::{
-    cli::InputParams,
-    cloud_params::get_cloud_params,
+    cli::{
+        input_params::{horizon::Horizon, shadow_method::ShadowMethod, time_range::TimeRange},
+        InputParams,
+    },
+    cloud_params::{get_cloud_params, CloudParams},
+    crs::{get_centroid_from_crs, get_meridian_convergence},
+    horizon::{build_height_grid, get_block_horizon, get_dem_horizon, read_dem, read_horizon_file},
     io::{Reader, Writer},
-    radiation::calculate_solar_radiation,
-    voxel::{build_voxel_grid, build_normals, get_voxel_block_iterator, Voxel, VoxelGrid},
+    radiation::{build_sky_view_factors, calculate_solar_radiation},
+    voxel::{
+        build_normals, build_voxel_grid, build_voxel_roles, get_voxel_block_iterator, Point,
+        Translation, Voxel, VoxelGrid,
+    },
 };
 
+// common year the typical meteorological year is placed in if no time range is given
+const TYPICAL_YEAR: i32 = 2021;
+
 pub fn pcsrt() -> Result<(), Box<dyn Error>> {
-    let input_params = InputParams::parse();
+    let mut input_params = InputParams::parse();
+
+    if input_params.extinction_coefficient.is_some()
+        && input_params.shadow_method != ShadowMethod::RayTracing
+    {
+        return Err(
+            "Vegetation transmission (extinction coefficient) requires ray-tracing shadow method"
+                .into(),
+        );
+    }
+
+    if input_params.shadow_samples > 1
+        && input_params.shadow_method != ShadowMethod::RayTracing
+        && !input_params.shadow_splatting
+    {
+        return Err(
+            "Shadow samples require ray-tracing shadow method or illumination map with shadow splatting"
+                .into(),
+        );
+    }
+
+    if let Some(tmy) = &input_params.tmy {
+        info!(
+            "TMY site: {:.3}, {:.3}, {:.0} m, UTC{:+}",
+            tmy.latitude, tmy.longitude, tmy.elevation, tmy.time_zone
+        );
+        // the typical year is placed in the year of the time range
+        let year = input_params
+            .time_range
+            .as_ref()
+            .map_or(TYPICAL_YEAR, |time_range| time_range.from.year());
+        if input_params.time_range.is_none() {
+            let (from, to) = tmy.get_time_span(year);
+            input_params.time_range = Some(TimeRange { from, to });
+        }
+        input_params.weather = Some(tmy.to_weather(year));
+    }
+
+    let mut reader = Reader::new(&input_params.input_file);
+
+    if input_params.centroid.is_none() {
+        info!("Deriving centroid from the input file CRS");
+        let point_source = reader.to_point_source()?;
+        let centroid = match (
+            get_centroid_from_crs(point_source.as_ref()),
+            &input_params.tmy,
+        ) {
+            (Ok(centroid), _) => centroid,
+            (Err(err), Some(tmy)) => {
+                info!(
+                    "Centroid not derived from the input file ({}), TMY site used",
+                    err
+                );
+                tmy.get_centroid()
+            }
+            (Err(err), None) => {
+                return Err(format!(
+                    "Centroid not specified and cannot be derived from the input file: {}",
+                    err
+                )
+                .into())
+            }
+        };
+        input_params.centroid = Some(centroid);
+    }
+
+    if input_params.meridian_convergence.is_none() {
+        let point_source = reader.to_point_source()?;
+        match get_meridian_convergence(point_source.as_ref()) {
+            Ok(meridian_convergence) => {
+                info!(
+                    "Meridian convergence derived from the input file CRS: {:.3}°",
+                    meridian_convergence
+                );
+                input_params.meridian_convergence = Some(meridian_convergence);
+            }
+            Err(err) => info!("Meridian convergence not applied: {}", err),
+        }
+    }
+
+    if let Some(weather) = &input_params.weather {
+        let time_range = input_params.get_time_range();
+        let (from, to) = weather.get_time_span();
+        info!(
+            "Weather records: {} ({} - {})",
+            weather.0.len(),
+            from.to_rfc3339(),
+            to.to_rfc3339()
+        );
+
+        let temperatures: Vec<f64> = weather
+            .0
+            .iter()
+            .filter(|record| (time_range.from..=time_range.to).contains(&record.time))
+            .filter_map(|record| record.temperature)
+            .collect();
+        if !temperatures.is_empty() {
+            info!(
+                "Mean air temperature in the time range: {:.1}°C",
+                temperatures.iter().sum::<f64>() / temperatures.len() as f64
+            );
+        }
+
+        if time_range.from < from || time_range.to > to {
+            warn!("Weather records do not cover the time range, clear-sky values are used outside of them");
+        }
+    }
+
+    if let Some(horizon_file) = &input_params.horizon_file {
+        info!("Reading horizon from \"{}\"", horizon_file);
+        input_params.horizon = read_horizon_file(horizon_file)?;
+    }
+
+    let mut occluder_readers: Vec<Reader> =
+        input_params.occluders.iter().map(Reader::new).collect();
 
     info!("Reading cloud params");
-    let reader = Reader::new(&input_params.input_file);
-    let cloud_params = get_cloud_params(&input_params, &reader)?;
+    let cloud_params = get_cloud_params(&input_params, &mut reader, &mut occluder_readers)?;
+
+    if let Some(horizon_dem) = &input_params.horizon_dem {
+        info!("Computing horizon from DEM \"{}\"", horizon_dem.path);
+        let dem = read_dem(horizon_dem)?;
+        let horizon = get_dem_horizon(
+            &dem,
+            &cloud_params.extent,
+            input_params.horizon_step,
+            input_params.horizon_distance,
+            input_params.meridian_convergence.unwrap_or(0.),
+        )?;
+        info!(
+            "Maximal horizon height: {:.1}°",
+            horizon.horizon_height.iter().cloned().fold(0., f64::max)
+        );
+        input_params.horizon = horizon;
+    }
 
     info!(
         "Computing solar radiation for:\nInput file: {}\nPoint count: {}\nAverage points: {}\nVoxel size: {}\nTime range: {} - {}\nTime step: {}min",
@@ -24,50 +169,181 @@ pub fn pcsrt() -> Result<(), Box<dyn Error>> {
         cloud_params.point_count,
         (cloud_params.average_points_in_voxel * 10.).round() / 10.,
         cloud_params.voxel_size,
-        input_params.time_range.from.to_rfc3339(),
-        input_params.time_range.to.to_rfc3339(),
+        input_params.get_time_range().from.to_rfc3339(),
+        input_params.get_time_range().to.to_rfc3339(),
         input_params.step_mins
     );
 
-    let mut writer = Writer::new(
-        &input_params.output_file,
-        input_params.output_ply_ascii,
-        &cloud_params,
-    )?;
+    let height_grid = match input_params.cloud_horizon {
+        Some(cell_size) => {
+            info!("Building height grid of the cloud for the block horizons");
+            Some(build_height_grid(
+                &reader,
+                &cloud_params.extent,
+                cell_size,
+                &input_params,
+            )?)
+        }
+        None => None,
+    };
+
+    let point_source = reader.to_point_source()?;
+    let mut writer = Writer::new(&input_params, &cloud_params, point_source.las_header())?;
 
-    let block_iterator = get_voxel_block_iterator(
+    let block_iterator = Mutex::new(get_voxel_block_iterator(
         &reader,
+        &occluder_readers,
         &cloud_params.extent,
-        input_params.block_process_params.clone().unwrap_or_default(),
-    );
+        cloud_params.block_params.clone(),
+        &input_params,
+    ));
+
+    let concurrent_blocks = get_concurrent_blocks(&input_params, &reader);
+    if concurrent_blocks > 1 {
+        info!("Processing {} blocks concurrently", concurrent_blocks);
+    }
+
+    // processed blocks are passed to the writer one by one, the workers wait until the writer is free
+    let (sender, receiver) =
+        mpsc::sync_channel::<Result<(VoxelGrid<Voxel>, Translation), String>>(0);
+
+    thread::scope(|scope| {
+        for _ in 0..concurrent_blocks {
+            let sender = sender.clone();
+            let block_iterator = &block_iterator;
+            let input_params = &input_params;
+            let cloud_params = &cloud_params;
+            let height_grid = &height_grid;
+
+            scope.spawn(move || loop {
+                let block = block_iterator.lock().unwrap().next();
+                let block = match block {
+                    Some(Ok(block)) => block,
+                    Some(Err(err)) => {
+                        sender.send(Err(err)).ok();
+                        break;
+                    }
+                    None => break,
+                };
+
+                if block.block_count > 1 {
+                    info!(
+                        "Processing cloud block {}/{}",
+                        block.block_number, block.block_count
+                    );
+                }
+
+                let block_horizon = height_grid
+                    .as_ref()
+                    .and_then(|height_grid| get_block_horizon(height_grid, &block, input_params));
+
+                let translation = block.translation;
+                let voxel_grid = process_block(
+                    block.points,
+                    &translation,
+                    input_params,
+                    cloud_params,
+                    block_horizon.as_ref(),
+                )
+                .map(|voxel_grid| (voxel_grid, translation))
+                .map_err(|err| err.to_string());
+
+                if sender.send(voxel_grid).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for processed_block in receiver {
+            let (voxel_grid, translation) = processed_block?;
 
-    for block in block_iterator {
-        if block.block_count > 1 {
             info!(
-                "Processing cloud block {}/{}",
-                block.block_number, block.block_count
+                "Writing solar radiation for block to file \"{}\"",
+                input_params.output_file.path
             );
+
+            writer.write(voxel_grid, &translation)?;
         }
-        let mut voxel_grid: VoxelGrid<Voxel> =
-            build_voxel_grid(block.points, cloud_params.voxel_size)?;
 
-        info!("Building normals for voxels");
-        let failed_normals = build_normals(&mut voxel_grid, cloud_params.average_points_in_voxel)?;
+        Ok(())
+    })
+}
 
-        if failed_normals > 0 {
-            warn!("Failed to construct normals on {} voxels.", failed_normals);
-        }
+fn process_block(
+    points: Vec<Point>,
+    translation: &Translation,
+    input_params: &InputParams,
+    cloud_params: &CloudParams,
+    block_horizon: Option<&Horizon>,
+) -> Result<VoxelGrid<Voxel>, Box<dyn Error>> {
+    let mut voxel_grid: VoxelGrid<Voxel> = build_voxel_grid(points, cloud_params.voxel_size)?;
 
-        info!("Calculating solar radiation");
-        calculate_solar_radiation(&voxel_grid, &input_params);
+    info!("Building normals for voxels");
+    let failed_normals = build_normals(&mut voxel_grid, cloud_params.average_points_in_voxel)?;
 
-        info!(
-            "Writing solar radiation for block to file \"{}\"",
-            input_params.output_file.path
-        );
+    if failed_normals > 0 {
+        warn!("Failed to construct normals on {} voxels.", failed_normals);
+    }
 
-        writer.write(voxel_grid, &block.translation)?;
+    let vegetation_voxels = build_voxel_roles(&mut voxel_grid, input_params);
+
+    if input_params.extinction_coefficient.is_some() && vegetation_voxels == 0 {
+        warn!("No vegetation voxels (see vegetation classes), all the occluders block the beam.");
     }
 
-    Ok(())
+    if input_params.sky_view_factor {
+        info!("Computing sky view factor of voxels");
+        build_sky_view_factors(&mut voxel_grid, input_params, cloud_params, block_horizon);
+    }
+
+    info!("Calculating solar radiation");
+    calculate_solar_radiation(
+        &voxel_grid,
+        input_params,
+        cloud_params,
+        translation,
+        block_horizon,
+    );
+
+    Ok(voxel_grid)
+}
+
+fn get_concurrent_blocks(input_params: &InputParams, reader: &Reader) -> usize {
+    let concurrent_blocks = input_params.concurrent_blocks.max(1);
+
+    match (input_params.memory_budget, reader.max_block_point_count()) {
+        (Some(memory_budget), Some(max_block_point_count)) => {
+            let block_memory = max_block_point_count * get_block_memory_per_point(input_params);
+            let memory_budget = memory_budget * 1024 * 1024;
+            let fitting_blocks = memory_budget / block_memory.max(1);
+
+            if fitting_blocks < concurrent_blocks {
+                warn!(
+                    "Memory budget allows processing only {} blocks concurrently.",
+                    fitting_blocks.max(1)
+                );
+            }
+            concurrent_blocks.min(fitting_blocks).max(1)
+        }
+        _ => concurrent_blocks,
+    }
+}
+
+/// Estimated memory taken by a point of a processed block, in the worst case of a voxel per point
+fn get_block_memory_per_point(input_params: &InputParams) -> usize {
+    // the point is moved from the block into its voxel
+    let point = size_of::<Point>();
+    // hash maps are counted twice for their spare capacity
+    let voxel = 2 * size_of::<((i64, i64, i64), Voxel)>();
+    // illumination maps of the sun positions processed in parallel
+    let map_cells = if input_params.shadow_splatting {
+        input_params.shadow_supersampling.ceil().powi(2) as usize
+    } else {
+        1
+    };
+    let illumination_map =
+        rayon::current_num_threads() * 2 * map_cells * size_of::<((i64, i64), (i64, usize))>();
+
+    point + voxel + illumination_map
 }
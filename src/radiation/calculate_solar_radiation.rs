@@ -3,51 +3,187 @@ use rayon::prelude::*;
 use std::rc::Rc;
 
 use super::illumination::{
-    get_rotated_voxel_key_pair_iterator, IlluminationMap, IlluminationMapUtils,
+    get_rotated_voxel_key_pair_iterator, DepthMap, DepthMapUtils, Footprint, IlluminationMap,
+    IlluminationMapUtils, RotatedVoxelKeyPair,
 };
-use super::radiation_components::get_irradiance;
 use super::radiation_components::VoxelIrradiance;
+use super::radiation_components::{get_irradiance, Elevation, GroundAlbedo};
+use super::ray_tracing::{get_lit_fraction, get_sun_direction, Transmission, VoxelGridBounds};
+use super::sample_offsets::get_sample_offsets;
 use super::sun_position::get_sun_positions;
+use crate::cli::input_params::{horizon::Horizon, shadow_method::ShadowMethod};
 use crate::cli::InputParams;
-use crate::voxel::Voxel;
+use crate::cloud_params::CloudParams;
 use crate::voxel::VoxelGrid;
+use crate::voxel::{Occluder, Translation, Voxel};
 
-pub fn calculate_solar_radiation(voxel_grid: &VoxelGrid<Voxel>, input_params: &InputParams) {
-    let sun_positions = get_sun_positions(input_params);
+pub fn calculate_solar_radiation(
+    voxel_grid: &VoxelGrid<Voxel>,
+    input_params: &InputParams,
+    cloud_params: &CloudParams,
+    translation: &Translation,
+    block_horizon: Option<&Horizon>,
+) {
+    let sun_positions = get_sun_positions(input_params, block_horizon);
     info!("Visible sun epochs: {}", sun_positions.len());
 
-    sun_positions.par_iter().for_each(|sun_position| {
-        let rot_voxel_key_pairs = get_rotated_voxel_key_pair_iterator(voxel_grid, sun_position);
+    let elevation = if input_params.centroid_elevation {
+        Elevation::Constant(input_params.get_centroid().elevation)
+    } else {
+        Elevation::Voxel {
+            voxel_size: cloud_params.voxel_size,
+            offset: translation.z + input_params.elevation_offset,
+        }
+    };
+
+    let ground_albedo = GroundAlbedo::new(input_params, voxel_grid);
+
+    let sample_offsets = get_sample_offsets(input_params.shadow_samples);
 
-        let voxel_illumination_map = IlluminationMap::create();
+    match input_params.shadow_method {
+        ShadowMethod::IlluminationMap if input_params.shadow_splatting => {
+            sun_positions.par_iter().for_each(|sun_position| {
+                let rot_voxel_key_pairs: Vec<RotatedVoxelKeyPair> =
+                    get_rotated_voxel_key_pair_iterator(
+                        voxel_grid,
+                        sun_position,
+                        input_params.shadow_supersampling,
+                    )
+                    .collect();
 
-        for rot_voxel_key_pair in rot_voxel_key_pairs {
-            let rot_voxel_key_pair = Rc::new(rot_voxel_key_pair);
-            if let Some(voxel_in_shadow) =
-                voxel_illumination_map.get_voxel_in_shadow(rot_voxel_key_pair)
-            {
-                let irradiance = get_irradiance(input_params, voxel_in_shadow, sun_position, true);
+                let footprint = Footprint::new(sun_position, input_params.shadow_supersampling);
+                let depth_map = DepthMap::create(&rot_voxel_key_pairs, sun_position, &footprint);
 
-                update_global_irradiance(voxel_in_shadow, &irradiance, true, sun_position.step_coef);
-            }
+                for rot_voxel_key_pair in rot_voxel_key_pairs
+                    .iter()
+                    .filter(|rot_voxel_key_pair| rot_voxel_key_pair.reference.role.receiver)
+                {
+                    let lit_fraction = depth_map.get_lit_fraction(
+                        rot_voxel_key_pair,
+                        sun_position,
+                        &footprint,
+                        &sample_offsets,
+                    );
+                    let irradiance = get_irradiance(
+                        input_params,
+                        rot_voxel_key_pair.reference,
+                        sun_position,
+                        lit_fraction,
+                        &elevation,
+                        &ground_albedo,
+                    );
+                    update_global_irradiance(&irradiance, lit_fraction, sun_position.step_coef);
+                }
+            });
         }
+        ShadowMethod::IlluminationMap => {
+            sun_positions.par_iter().for_each(|sun_position| {
+                let rot_voxel_key_pairs = get_rotated_voxel_key_pair_iterator(
+                    voxel_grid,
+                    sun_position,
+                    input_params.shadow_supersampling,
+                );
+
+                let voxel_illumination_map = IlluminationMap::create();
+                // voxels not casting shadows are checked against the complete map
+                let mut non_occluder_key_pairs = vec![];
+
+                for rot_voxel_key_pair in rot_voxel_key_pairs {
+                    let role = rot_voxel_key_pair.reference.role;
+                    if role.occluder == Occluder::None {
+                        if role.receiver {
+                            non_occluder_key_pairs.push(rot_voxel_key_pair);
+                        }
+                        continue;
+                    }
+
+                    let rot_voxel_key_pair = Rc::new(rot_voxel_key_pair);
+                    if let Some(voxel_in_shadow) = voxel_illumination_map
+                        .get_voxel_in_shadow(rot_voxel_key_pair)
+                        .filter(|voxel| voxel.role.receiver)
+                    {
+                        let irradiance = get_irradiance(
+                            input_params,
+                            voxel_in_shadow,
+                            sun_position,
+                            0.,
+                            &elevation,
+                            &ground_albedo,
+                        );
 
-        for (_z, illuminated_voxel) in voxel_illumination_map.borrow_mut().values() {
-            let irradiance = get_irradiance(input_params, illuminated_voxel, sun_position, false);
-            update_global_irradiance(illuminated_voxel, &irradiance, false, sun_position.step_coef);
+                        update_global_irradiance(&irradiance, 0., sun_position.step_coef);
+                    }
+                }
+
+                for (_z, illuminated_voxel) in voxel_illumination_map.borrow_mut().values() {
+                    if !illuminated_voxel.role.receiver {
+                        continue;
+                    }
+                    let irradiance = get_irradiance(
+                        input_params,
+                        illuminated_voxel,
+                        sun_position,
+                        1.,
+                        &elevation,
+                        &ground_albedo,
+                    );
+                    update_global_irradiance(&irradiance, 1., sun_position.step_coef);
+                }
+
+                for rot_voxel_key_pair in non_occluder_key_pairs {
+                    let lit_fraction = if voxel_illumination_map.is_in_shadow(&rot_voxel_key_pair) {
+                        0.
+                    } else {
+                        1.
+                    };
+                    let irradiance = get_irradiance(
+                        input_params,
+                        rot_voxel_key_pair.reference,
+                        sun_position,
+                        lit_fraction,
+                        &elevation,
+                        &ground_albedo,
+                    );
+                    update_global_irradiance(&irradiance, lit_fraction, sun_position.step_coef);
+                }
+            });
+        }
+        ShadowMethod::RayTracing => {
+            let bounds = VoxelGridBounds::new(voxel_grid);
+            let transmission = Transmission::new(input_params, cloud_params);
+
+            sun_positions.par_iter().for_each(|sun_position| {
+                let sun_direction = get_sun_direction(sun_position);
+
+                for voxel in voxel_grid.values().filter(|voxel| voxel.role.receiver) {
+                    let lit_fraction = get_lit_fraction(
+                        voxel_grid,
+                        &bounds,
+                        voxel,
+                        &sun_direction,
+                        &sample_offsets,
+                        transmission.as_ref(),
+                    );
+                    let irradiance = get_irradiance(
+                        input_params,
+                        voxel,
+                        sun_position,
+                        lit_fraction,
+                        &elevation,
+                        &ground_albedo,
+                    );
+                    update_global_irradiance(&irradiance, lit_fraction, sun_position.step_coef);
+                }
+            });
         }
-    });
+    }
 }
 
-fn update_global_irradiance(
-    voxel: &Voxel,
-    irradiance: &VoxelIrradiance,
-    in_shadow: bool,
-    step_coef: f64,
-) {
-    let mut irradiation = voxel.irradiation.write().unwrap();
+fn update_global_irradiance(irradiance: &VoxelIrradiance, lit_fraction: f64, step_coef: f64) {
+    let mut irradiation = irradiance.voxel.irradiation.write().unwrap();
     irradiation.global_irradiance += irradiance.global_irradiance * step_coef;
     irradiation.beam_component += irradiance.beam_component * step_coef;
     irradiation.diffuse_component += irradiance.diffuse_component * step_coef;
-    irradiation.sun_hours += if in_shadow { 0. } else { 1. * step_coef };
+    irradiation.reflected_component += irradiance.reflected_component * step_coef;
+    irradiation.sun_hours += lit_fraction * step_coef;
 }
@@ -1,4 +1,6 @@
-use crate::voxel::{Key, Voxel};
+use crate::radiation::sun_position::SunPosition;
+use crate::voxel::{Key, Occluder, Voxel};
+use nalgebra::Vector3;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
@@ -22,6 +24,8 @@ pub trait IlluminationMapUtils<'a> {
         &'a self,
         rot_voxel_key_pair: Rc<RotatedVoxelKeyPair<'a>>,
     ) -> Option<&'a Voxel>;
+
+    fn is_in_shadow(&self, rot_voxel_key_pair: &RotatedVoxelKeyPair) -> bool;
 }
 
 impl<'a> IlluminationMapUtils<'a> for IlluminationMap<'a> {
@@ -61,4 +65,233 @@ impl<'a> IlluminationMapUtils<'a> for IlluminationMap<'a> {
             None
         }
     }
+
+    fn is_in_shadow(&self, rot_voxel_key_pair: &RotatedVoxelKeyPair) -> bool {
+        let (x, y, z) = rot_voxel_key_pair.rotated_key.as_tuple();
+        self.borrow()
+            .get(&(x, y))
+            .is_some_and(|(occluder_z, _)| *occluder_z < z)
+    }
+}
+
+const FOOTPRINT_TOLERANCE: f64 = 1e-9;
+
+/// Voxel cube projected to the illumination map - half-size (in cells) in the map plane
+/// and the depth of the cube along the sun direction
+#[derive(Debug, Clone, Copy)]
+pub struct Footprint {
+    pub x: i64,
+    pub y: i64,
+    pub depth: i64,
+}
+
+impl Footprint {
+    pub fn new(sun_position: &SunPosition, supersampling: f64) -> Self {
+        let rotation = sun_position.rotation_x * sun_position.rotation_z;
+        let matrix = rotation.matrix();
+        let get_half_size = |row: usize| {
+            (0..3).map(|col| matrix[(row, col)].abs()).sum::<f64>() / 2. * supersampling
+        };
+
+        // float error of the rotation shouldn't add or remove a cell
+        Footprint {
+            x: (get_half_size(0) + FOOTPRINT_TOLERANCE).floor() as i64,
+            y: (get_half_size(1) + FOOTPRINT_TOLERANCE).floor() as i64,
+            // voxels of the same surface shouldn't shadow each other
+            depth: (get_half_size(2) * 2. - FOOTPRINT_TOLERANCE).ceil() as i64 + 1,
+        }
+    }
+
+    // depth of the voxel surface (plane given by the rotated normal) in the cell
+    // relative to the voxel center, limited by the voxel cube
+    fn get_surface_depth_offset(&self, normal: &Vector3<f64>, cell_x: i64, cell_y: i64) -> i64 {
+        let max_depth_offset = self.depth as f64 / 2.;
+        let depth_offset = if normal.z.abs() > f64::EPSILON {
+            -(normal.x * cell_x as f64 + normal.y * cell_y as f64) / normal.z
+        } else {
+            0.
+        };
+        depth_offset
+            .clamp(-max_depth_offset, max_depth_offset)
+            .round() as i64
+    }
+}
+
+fn get_rotated_normal(
+    rot_voxel_key_pair: &RotatedVoxelKeyPair,
+    sun_position: &SunPosition,
+) -> Vector3<f64> {
+    sun_position.rotation_x
+        * sun_position.rotation_z
+        * rot_voxel_key_pair.reference.normal_vector.as_na_vec()
+}
+
+/// Lowest rotated z of the splatted voxel footprints in each cell
+pub type DepthMap = HashMap<(i64, i64), i64, BuildHasherDefault<XxHash64>>;
+
+pub trait DepthMapUtils {
+    fn create(
+        rot_voxel_key_pairs: &[RotatedVoxelKeyPair],
+        sun_position: &SunPosition,
+        footprint: &Footprint,
+    ) -> DepthMap {
+        let mut depth_map = DepthMap::default();
+        for rot_voxel_key_pair in rot_voxel_key_pairs.iter().filter(|rot_voxel_key_pair| {
+            rot_voxel_key_pair.reference.role.occluder != Occluder::None
+        }) {
+            let (x, y, voxel_z) = rot_voxel_key_pair.rotated_key.as_tuple();
+            let normal = get_rotated_normal(rot_voxel_key_pair, sun_position);
+            for cell_x in -footprint.x..=footprint.x {
+                for cell_y in -footprint.y..=footprint.y {
+                    let z = voxel_z + footprint.get_surface_depth_offset(&normal, cell_x, cell_y);
+                    depth_map
+                        .entry((x + cell_x, y + cell_y))
+                        .and_modify(|depth| *depth = (*depth).min(z))
+                        .or_insert(z);
+                }
+            }
+        }
+        depth_map
+    }
+
+    fn get_lit_fraction(
+        &self,
+        rot_voxel_key_pair: &RotatedVoxelKeyPair,
+        sun_position: &SunPosition,
+        footprint: &Footprint,
+        sample_offsets: &[f64],
+    ) -> f64;
+}
+
+impl DepthMapUtils for DepthMap {
+    // the footprint cells of the voxel are sampled on the n x n grid,
+    // without sub-samples only the center of the voxel is tested
+    fn get_lit_fraction(
+        &self,
+        rot_voxel_key_pair: &RotatedVoxelKeyPair,
+        sun_position: &SunPosition,
+        footprint: &Footprint,
+        sample_offsets: &[f64],
+    ) -> f64 {
+        let (x, y, z) = rot_voxel_key_pair.rotated_key.as_tuple();
+        let get_cell_offset =
+            |offset: f64, half_size: i64| (offset * (2 * half_size + 1) as f64).round() as i64;
+
+        // the voxel surface shouldn't shadow itself
+        let normal = get_rotated_normal(rot_voxel_key_pair, sun_position);
+
+        let mut lit_cells = 0;
+        for offset_x in sample_offsets {
+            for offset_y in sample_offsets {
+                let cell_x = get_cell_offset(*offset_x, footprint.x);
+                let cell_y = get_cell_offset(*offset_y, footprint.y);
+                let surface_depth = z + footprint.get_surface_depth_offset(&normal, cell_x, cell_y);
+                let in_shadow = self
+                    .get(&(x + cell_x, y + cell_y))
+                    .is_some_and(|depth| surface_depth - depth > footprint.depth);
+                if !in_shadow {
+                    lit_cells += 1;
+                }
+            }
+        }
+
+        lit_cells as f64 / sample_offsets.len().pow(2) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::constants::PI;
+    use crate::radiation::illumination::get_rotated_voxel_key_pair_iterator;
+    use crate::radiation::sample_offsets::get_sample_offsets;
+    use crate::voxel::VoxelGrid;
+    use chrono::{TimeZone, Utc};
+
+    fn get_voxel_grid(keys: &[(i64, i64, i64)]) -> VoxelGrid<Voxel> {
+        keys.iter()
+            .map(|&(x, y, z)| ((x, y, z), Voxel::from_test_key(x, y, z)))
+            .collect()
+    }
+
+    // sun in the south
+    fn get_sun_position(altitude: f64) -> SunPosition {
+        let time = Utc.ymd(2020, 6, 21).and_hms(10, 0, 0);
+        SunPosition::new(time, PI, altitude.to_radians(), 0., 1.)
+    }
+
+    #[test]
+    fn footprint_covers_voxel_cross_section() {
+        let footprint = Footprint::new(&get_sun_position(90.), 4.);
+        assert_eq!((footprint.x, footprint.y, footprint.depth), (2, 2, 5));
+
+        let footprint = Footprint::new(&get_sun_position(90.), 1.);
+        assert_eq!((footprint.x, footprint.y, footprint.depth), (0, 0, 2));
+    }
+
+    #[test]
+    fn splatted_occluder_shades_neighbouring_cells() {
+        let supersampling = 4.;
+        // the shadow of the occluder is shifted by 0.3 voxel to the north
+        let sun_position = get_sun_position((5f64 / 0.3).atan().to_degrees());
+        let voxel_grid = get_voxel_grid(&[(0, 0, 5), (0, 0, 0), (0, 3, 0)]);
+
+        let rot_voxel_key_pairs: Vec<RotatedVoxelKeyPair> =
+            get_rotated_voxel_key_pair_iterator(&voxel_grid, &sun_position, supersampling)
+                .collect();
+        let get_rot_voxel_key_pair = |key| {
+            rot_voxel_key_pairs
+                .iter()
+                .find(|pair| (pair.reference.x, pair.reference.y, pair.reference.z) == key)
+                .unwrap()
+        };
+
+        let footprint = Footprint::new(&sun_position, supersampling);
+        let depth_map = DepthMap::create(&rot_voxel_key_pairs, &sun_position, &footprint);
+
+        let occluder_key = get_rot_voxel_key_pair((0, 0, 5)).rotated_key;
+        let receiver = get_rot_voxel_key_pair((0, 0, 0));
+        // the centers fall into different cells, only the footprint of the occluder covers it
+        assert_ne!(
+            (occluder_key.x, occluder_key.y),
+            (receiver.rotated_key.x, receiver.rotated_key.y)
+        );
+        assert!(depth_map.contains_key(&(receiver.rotated_key.x, receiver.rotated_key.y)));
+
+        let get_lit_fraction = |rot_voxel_key_pair| {
+            depth_map.get_lit_fraction(rot_voxel_key_pair, &sun_position, &footprint, &[0.])
+        };
+        assert_eq!(get_lit_fraction(receiver), 0.);
+        assert_eq!(get_lit_fraction(get_rot_voxel_key_pair((0, 3, 0))), 1.);
+        assert_eq!(get_lit_fraction(get_rot_voxel_key_pair((0, 0, 5))), 1.);
+    }
+
+    #[test]
+    fn half_covered_voxel_is_half_lit() {
+        // the shadow of the occluder row is shifted by half a voxel to the north
+        let sun_position = get_sun_position((5f64 / 0.5).atan().to_degrees());
+        let mut keys: Vec<(i64, i64, i64)> = (-5..=5).map(|x| (x, 0, 5)).collect();
+        keys.push((0, 0, 0));
+        let voxel_grid = get_voxel_grid(&keys);
+
+        for supersampling in [2., 4.] {
+            let rot_voxel_key_pairs: Vec<RotatedVoxelKeyPair> =
+                get_rotated_voxel_key_pair_iterator(&voxel_grid, &sun_position, supersampling)
+                    .collect();
+            let footprint = Footprint::new(&sun_position, supersampling);
+            let depth_map = DepthMap::create(&rot_voxel_key_pairs, &sun_position, &footprint);
+            let receiver = rot_voxel_key_pairs
+                .iter()
+                .find(|pair| pair.reference.z == 0)
+                .unwrap();
+
+            let lit_fraction = depth_map.get_lit_fraction(
+                receiver,
+                &sun_position,
+                &footprint,
+                &get_sample_offsets(2),
+            );
+            assert_eq!(lit_fraction, 0.5, "{}", supersampling);
+        }
+    }
 }
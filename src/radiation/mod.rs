@@ -1,6 +1,11 @@
 mod calculate_solar_radiation;
 mod illumination;
 mod radiation_components;
+mod ray_tracing;
+mod sample_offsets;
+mod shadow_halo;
 mod sun_position;
 
 pub use self::calculate_solar_radiation::calculate_solar_radiation;
+pub use self::ray_tracing::build_sky_view_factors;
+pub use self::shadow_halo::get_shadow_halo;
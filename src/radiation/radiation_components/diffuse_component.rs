@@ -1,16 +1,127 @@
-use std::f64::consts::PI;
-
 use nalgebra::Vector3;
 
-use crate::common::constants::SOLAR_CONSTANT;
+use super::{get_beam_irradiance, SkyConditions};
+use crate::cli::input_params::diffuse_model::DiffuseModel;
+use crate::common::constants::{PI, SOLAR_CONSTANT};
+
+// cos(85°) - the circumsolar term is limited near the horizon (Perez 1990)
+const MIN_COS_ZENITH: f64 = 0.087_155_743;
 
+// Perez 1990 brightening coefficients (F11, F12, F13, F21, F22, F23) of the sky clearness bins
+const PEREZ_CLEARNESS_BINS: [f64; 7] = [1.065, 1.23, 1.5, 1.95, 2.8, 4.5, 6.2];
+const PEREZ_COEFFICIENTS: [[f64; 6]; 8] = [
+    [-0.008, 0.588, -0.062, -0.060, 0.072, -0.022],
+    [0.130, 0.683, -0.151, -0.019, 0.066, -0.029],
+    [0.330, 0.487, -0.221, 0.055, -0.064, -0.026],
+    [0.568, 0.187, -0.295, 0.109, -0.152, -0.014],
+    [0.873, -0.392, -0.362, 0.226, -0.462, 0.001],
+    [1.132, -1.237, -0.412, 0.288, -0.823, 0.056],
+    [1.060, -1.600, -0.359, 0.264, -1.127, 0.131],
+    [0.678, -0.327, -0.250, 0.156, -1.377, 0.251],
+];
+
+/// Diffuse irradiance on the tilted surface, the beam component is None for shaded surfaces
 pub fn get_diffuse_irradiance(
+    diffuse_model: DiffuseModel,
     solar_altitude: f64,
     incline_angle: f64,
     normal_vector: Vector3<f64>,
+    sky_conditions: &SkyConditions,
+    beam_component: Option<f64>,
+) -> f64 {
+    let solar_distance_variation_correction = sky_conditions.solar_distance_variation_correction;
+    let linke_turbidity_factor = sky_conditions.linke_turbidity_factor;
+    let weather_scale = sky_conditions.weather_scale;
+
+    let diffuse_horizontal = get_horizontal_diffuse_irradiance(
+        solar_altitude,
+        solar_distance_variation_correction,
+        linke_turbidity_factor,
+    ) * weather_scale.diffuse;
+    let extraterrestrial = SOLAR_CONSTANT * solar_distance_variation_correction;
+    let get_beam_normal = || {
+        get_beam_irradiance(
+            sky_conditions.elevation,
+            solar_altitude,
+            PI / 2.,
+            solar_distance_variation_correction,
+            linke_turbidity_factor,
+        ) * weather_scale.beam
+    };
+
+    let slope = normal_vector.angle(&Vector3::z());
+    let sky_view = (1. + slope.cos()) / 2.;
+    // circumsolar region is shaded together with the beam
+    let circumsolar_ratio = if beam_component.is_some() {
+        normal_vector.dot(&sky_conditions.sun_direction).max(0.)
+            / solar_altitude.sin().max(MIN_COS_ZENITH)
+    } else {
+        0.
+    };
+
+    match diffuse_model {
+        DiffuseModel::Esra => {
+            get_esra_diffuse_irradiance(
+                solar_altitude,
+                incline_angle,
+                normal_vector,
+                solar_distance_variation_correction,
+                linke_turbidity_factor,
+                beam_component,
+            ) * weather_scale.diffuse
+        }
+        DiffuseModel::Isotropic => diffuse_horizontal * sky_view,
+        DiffuseModel::HayDavies => {
+            let anisotropy_index = (get_beam_normal() / extraterrestrial).min(1.);
+            diffuse_horizontal
+                * ((1. - anisotropy_index) * sky_view + anisotropy_index * circumsolar_ratio)
+        }
+        DiffuseModel::Perez if diffuse_horizontal > 0. => {
+            let (circumsolar, horizon) = get_perez_brightening(
+                solar_altitude,
+                diffuse_horizontal,
+                get_beam_normal(),
+                extraterrestrial,
+            );
+            (diffuse_horizontal
+                * ((1. - circumsolar) * sky_view
+                    + circumsolar * circumsolar_ratio
+                    + horizon * slope.sin()))
+            .max(0.)
+        }
+        DiffuseModel::Perez => 0.,
+    }
+}
+
+/// Circumsolar (F1) & horizon (F2) brightening coefficients of the Perez 1990 model
+fn get_perez_brightening(
+    solar_altitude: f64,
+    diffuse_horizontal: f64,
+    beam_normal: f64,
+    extraterrestrial: f64,
+) -> (f64, f64) {
+    let zenith_angle = PI / 2. - solar_altitude;
+    let zenith_term = 1.041 * zenith_angle.powi(3);
+    let clearness = ((diffuse_horizontal + beam_normal) / diffuse_horizontal + zenith_term)
+        / (1. + zenith_term);
+
+    let relative_optical_air_mass = 1.
+        / (solar_altitude.sin() + 0.50572 * (solar_altitude.to_degrees() + 6.07995).powf(-1.6364));
+    let brightness = diffuse_horizontal * relative_optical_air_mass / extraterrestrial;
+
+    let bin = PEREZ_CLEARNESS_BINS.partition_point(|limit| clearness >= *limit);
+    let [f11, f12, f13, f21, f22, f23] = PEREZ_COEFFICIENTS[bin];
+
+    (
+        (f11 + f12 * brightness + f13 * zenith_angle).max(0.),
+        f21 + f22 * brightness + f23 * zenith_angle,
+    )
+}
+
+pub(super) fn get_horizontal_diffuse_irradiance(
+    solar_altitude: f64,
     solar_distance_variation_correction: f64,
     linke_turbidity_factor: f64,
-    beam_component: Option<f64>,
 ) -> f64 {
     let diffuse_transmission_function = -0.015_843
         + 0.030_543 * linke_turbidity_factor
@@ -34,10 +145,25 @@ pub fn get_diffuse_irradiance(
         a_0 + a_1 * solar_altitude.sin() + a_2 * solar_altitude.sin().powf(2.)
     };
 
-    let diffuse_irradiance = SOLAR_CONSTANT
+    SOLAR_CONSTANT
         * solar_distance_variation_correction
         * diffuse_transmission_function
-        * diffuse_anglular_function;
+        * diffuse_anglular_function
+}
+
+fn get_esra_diffuse_irradiance(
+    solar_altitude: f64,
+    incline_angle: f64,
+    normal_vector: Vector3<f64>,
+    solar_distance_variation_correction: f64,
+    linke_turbidity_factor: f64,
+    beam_component: Option<f64>,
+) -> f64 {
+    let diffuse_irradiance = get_horizontal_diffuse_irradiance(
+        solar_altitude,
+        solar_distance_variation_correction,
+        linke_turbidity_factor,
+    );
     let slope: f64 = normal_vector.angle(&Vector3::from([normal_vector[0], normal_vector[1], 1.])); // TODO
 
     if let Some(beam_radiation) = beam_component {
@@ -69,3 +195,97 @@ fn diffuse_function(slope: f64, n: f64) -> f64 {
     ((1. + slope.cos()) / 2.)
         + (slope.sin() - slope * slope.cos() - PI * (slope / 2.).sin().powf(2.)) * n
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiation::radiation_components::WeatherScale;
+
+    const SOLAR_ALTITUDE: f64 = 40. * PI / 180.;
+
+    fn get_sky_conditions() -> SkyConditions {
+        SkyConditions {
+            solar_distance_variation_correction: 1.,
+            linke_turbidity_factor: 3.,
+            elevation: 200.,
+            weather_scale: WeatherScale::default(),
+            sun_direction: Vector3::new(SOLAR_ALTITUDE.cos(), 0., SOLAR_ALTITUDE.sin()),
+        }
+    }
+
+    fn get_diffuse(
+        diffuse_model: DiffuseModel,
+        normal_vector: Vector3<f64>,
+        beam_component: Option<f64>,
+    ) -> f64 {
+        get_diffuse_irradiance(
+            diffuse_model,
+            SOLAR_ALTITUDE,
+            SOLAR_ALTITUDE,
+            normal_vector,
+            &get_sky_conditions(),
+            beam_component,
+        )
+    }
+
+    #[test]
+    fn horizontal_surface_gets_horizontal_diffuse() {
+        let diffuse_horizontal = get_horizontal_diffuse_irradiance(SOLAR_ALTITUDE, 1., 3.);
+        assert!(diffuse_horizontal > 0.);
+
+        for diffuse_model in [
+            DiffuseModel::Isotropic,
+            DiffuseModel::HayDavies,
+            DiffuseModel::Perez,
+        ] {
+            let diffuse = get_diffuse(diffuse_model, Vector3::z(), Some(500.));
+            assert!((diffuse - diffuse_horizontal).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn perez_clearness_bins_include_lower_limit() {
+        // at the zenith the clearness is (diffuse + beam normal) / diffuse,
+        // without the brightness the horizon brightening is F21 of the bin
+        let get_bin = |beam_normal: f64| {
+            let (_, horizon) = get_perez_brightening(PI / 2., 100., beam_normal, f64::INFINITY);
+            PEREZ_COEFFICIENTS
+                .iter()
+                .position(|coefficients| coefficients[3] == horizon)
+                .unwrap()
+        };
+
+        assert_eq!(get_bin(0.), 0);
+        assert_eq!(get_bin(6.499), 0);
+        assert_eq!(get_bin(6.5), 1);
+        assert_eq!(get_bin(49.999), 2);
+        assert_eq!(get_bin(50.), 3);
+        assert_eq!(get_bin(519.999), 6);
+        assert_eq!(get_bin(520.), 7);
+        assert_eq!(get_bin(10000.), 7);
+    }
+
+    #[test]
+    fn circumsolar_term_drops_out_of_shaded_surfaces() {
+        // 60° slopes facing the sun at 40° altitude & facing away from it
+        let slope = 60. * PI / 180.;
+        let sunward = Vector3::new(slope.sin(), 0., slope.cos());
+        let away = Vector3::new(-slope.sin(), 0., slope.cos());
+
+        for diffuse_model in [DiffuseModel::HayDavies, DiffuseModel::Perez] {
+            let lit = get_diffuse(diffuse_model, sunward, Some(500.));
+            let shaded = get_diffuse(diffuse_model, sunward, None);
+            let away_lit = get_diffuse(diffuse_model, away, Some(500.));
+            assert!(lit > shaded);
+            // only the circumsolar term depends on the orientation to the sun
+            assert!((shaded - away_lit).abs() < 1e-9);
+            assert!((get_diffuse(diffuse_model, away, None) - away_lit).abs() < 1e-9);
+        }
+
+        let isotropic = get_diffuse(DiffuseModel::Isotropic, sunward, Some(500.));
+        assert_eq!(
+            isotropic,
+            get_diffuse(DiffuseModel::Isotropic, sunward, None)
+        );
+    }
+}
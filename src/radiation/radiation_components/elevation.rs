@@ -0,0 +1,17 @@
+use crate::voxel::Voxel;
+
+/// Elevation above sea level used in the air mass correction of the beam component
+pub enum Elevation {
+    Constant(f64),
+    Voxel { voxel_size: f64, offset: f64 },
+}
+
+impl Elevation {
+    pub fn get(&self, voxel: &Voxel) -> f64 {
+        match *self {
+            Elevation::Constant(elevation) => elevation,
+            // voxel key is the voxel center in voxel size units
+            Elevation::Voxel { voxel_size, offset } => voxel.z as f64 * voxel_size + offset,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use twox_hash::XxHash64;
+
+use crate::cli::input_params::albedo::Albedo;
+use crate::cli::InputParams;
+use crate::voxel::{Voxel, VoxelGrid};
+
+type ColumnClasses = HashMap<(i64, i64), (f64, Option<u8>), BuildHasherDefault<XxHash64>>;
+
+/// Albedo of the ground reflecting to the voxels, per-class albedo is given by the lowest point
+/// in the voxel column (the roof itself if there are no ground points below it)
+pub enum GroundAlbedo<'a> {
+    None,
+    Monthly(&'a Albedo),
+    Classes {
+        albedo: &'a Albedo,
+        column_classes: ColumnClasses,
+    },
+}
+
+impl<'a> GroundAlbedo<'a> {
+    pub fn new(input_params: &'a InputParams, voxel_grid: &VoxelGrid<Voxel>) -> Self {
+        match &input_params.albedo {
+            None => GroundAlbedo::None,
+            Some(albedo @ Albedo::Monthly(_)) => GroundAlbedo::Monthly(albedo),
+            Some(albedo @ Albedo::Classes { .. }) => {
+                let mut column_classes = ColumnClasses::default();
+                for voxel in voxel_grid.values() {
+                    for point in &voxel.points {
+                        let classification = point.attributes.classification;
+                        column_classes
+                            .entry((voxel.x, voxel.y))
+                            .and_modify(|(z, class)| {
+                                if point.z < *z {
+                                    *z = point.z;
+                                    *class = classification;
+                                }
+                            })
+                            .or_insert((point.z, classification));
+                    }
+                }
+                GroundAlbedo::Classes {
+                    albedo,
+                    column_classes,
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, voxel: &Voxel, month: u32) -> Option<f64> {
+        match self {
+            GroundAlbedo::None => None,
+            GroundAlbedo::Monthly(albedo) => Some(albedo.get_val(month, None)),
+            GroundAlbedo::Classes {
+                albedo,
+                column_classes,
+            } => {
+                let classification = column_classes
+                    .get(&(voxel.x, voxel.y))
+                    .and_then(|(_, classification)| *classification);
+                Some(albedo.get_val(month, classification))
+            }
+        }
+    }
+}
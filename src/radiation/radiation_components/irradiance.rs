@@ -1,31 +1,31 @@
-use std::f64::consts::PI;
-
 use crate::cli::InputParams;
+use crate::common::constants::PI;
+use crate::radiation::ray_tracing::get_sun_direction;
 use crate::radiation::sun_position::SunPosition;
 use crate::voxel::Voxel;
 
-use super::VoxelIrradiance;
-use super::{get_beam_irradiance, get_diffuse_irradiance};
-use chrono::{Datelike, TimeZone, Utc};
+use super::{get_beam_irradiance, get_diffuse_irradiance, get_reflected_irradiance};
+use super::{Elevation, GroundAlbedo, SkyConditions, VoxelIrradiance};
+use chrono::{DateTime, Datelike, Utc};
 use nalgebra::Vector3;
 
 pub fn get_irradiance<'a>(
     input_params: &InputParams,
     voxel: &'a Voxel,
     sun_position: &SunPosition,
-    in_shadow: bool,
+    lit_fraction: f64,
+    elevation: &Elevation,
+    ground_albedo: &GroundAlbedo,
 ) -> VoxelIrradiance<'a> {
-    let no_of_day = f64::from(
-        Utc.timestamp_millis(sun_position.time.timestamp_millis())
-            .ordinal0(),
-    ); // todo check if coorect
     let month = sun_position.time.month();
     let linke_turbidity_factor = input_params.linke_turbidity_factor.get_val(month);
 
     let solar_altitude = sun_position.altitude;
-    let solar_azimuth = sun_position.azimuth;
-    let elevation = input_params.centroid.elevation;
-    let solar_distance_variation_correction = solar_distance_variation_correction(no_of_day);
+    let solar_azimuth = sun_position.grid_azimuth;
+    let elevation = elevation.get(voxel);
+    let solar_distance_variation_correction =
+        solar_distance_variation_correction(&sun_position.time);
+    let weather_scale = sun_position.weather_scale;
 
     let zenith_angle = (PI / 2.) - solar_altitude;
     let sun_direction = Vector3::from([
@@ -39,42 +39,119 @@ pub fn get_irradiance<'a>(
         incline_angle += PI / 2.;
     };
 
-    let beam_component = if !in_shadow {
-        Some(get_beam_irradiance(
-            elevation,
-            solar_altitude,
-            incline_angle,
-            solar_distance_variation_correction,
-            linke_turbidity_factor,
-        ))
+    let beam_component = if lit_fraction > 0. {
+        Some(
+            get_beam_irradiance(
+                elevation,
+                solar_altitude,
+                incline_angle,
+                solar_distance_variation_correction,
+                linke_turbidity_factor,
+            ) * weather_scale.beam,
+        )
     } else {
         None
     };
 
-    let diffuse_component = get_diffuse_irradiance(
-        solar_altitude,
-        incline_angle,
-        voxel.normal_vector.as_na_vec(),
+    let sky_conditions = SkyConditions {
         solar_distance_variation_correction,
         linke_turbidity_factor,
-        beam_component,
-    );
+        elevation,
+        weather_scale,
+        sun_direction: get_sun_direction(sun_position),
+    };
+    let get_diffuse_component = |beam_component| {
+        get_diffuse_irradiance(
+            input_params.diffuse_model,
+            solar_altitude,
+            incline_angle,
+            voxel.normal_vector.as_na_vec(),
+            &sky_conditions,
+            beam_component,
+        )
+    };
 
-    let beam_component = beam_component.unwrap_or(0.);
-    let diffuse_component = diffuse_component;
+    // partially shaded voxel mixes the sunlit and the shaded diffuse model
+    let diffuse_component = if lit_fraction > 0. && lit_fraction < 1. {
+        lit_fraction * get_diffuse_component(beam_component)
+            + (1. - lit_fraction) * get_diffuse_component(None)
+    } else {
+        get_diffuse_component(beam_component)
+    } * voxel.sky_view_factor;
 
-    let global_irradiance = beam_component + diffuse_component;
+    let beam_component = beam_component.unwrap_or(0.) * lit_fraction;
+
+    // ground reflection is not shaded
+    let reflected_component = ground_albedo
+        .get(voxel, month)
+        .map(|albedo| {
+            get_reflected_irradiance(
+                albedo,
+                solar_altitude,
+                voxel.normal_vector.as_na_vec(),
+                &sky_conditions,
+            )
+        })
+        .unwrap_or(0.);
+
+    let global_irradiance = beam_component + diffuse_component + reflected_component;
 
     VoxelIrradiance {
         voxel,
         global_irradiance,
         beam_component,
         diffuse_component,
+        reflected_component,
     }
 }
 
-fn solar_distance_variation_correction(no_of_day: f64) -> f64 {
+pub fn solar_distance_variation_correction(time: &DateTime<Utc>) -> f64 {
+    let no_of_day = f64::from(time.ordinal0());
     let j = 2. * PI * no_of_day / 365.25; // no_of_day - 1 verify january 1.
     1. + 0.034221 * (j - 0.048869).cos() // epsilon
                                          // todo: verify - https://rredc.nrel.gov/solar/pubs/spectral/model/section2.html
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn higher_voxel_gets_more_beam() {
+        let input_params = InputParams::from_test_args(&[]);
+        let sun_position = SunPosition::new(
+            Utc.ymd(2020, 6, 21).and_hms(8, 0, 0),
+            110f64.to_radians(),
+            20f64.to_radians(),
+            0.,
+            1.,
+        );
+        // block translation (150 m) & the elevation offset (50 m)
+        let elevation = Elevation::Voxel {
+            voxel_size: 0.5,
+            offset: 150. + 50.,
+        };
+        let low_voxel = Voxel::from_test_key(0, 0, 0);
+        let high_voxel = Voxel::from_test_key(0, 0, 2000);
+        assert_eq!(elevation.get(&low_voxel), 200.);
+        assert_eq!(elevation.get(&high_voxel), 1200.);
+
+        let get_beam = |voxel, elevation| {
+            get_irradiance(
+                &input_params,
+                voxel,
+                &sun_position,
+                1.,
+                elevation,
+                &GroundAlbedo::None,
+            )
+            .beam_component
+        };
+        let low_beam = get_beam(&low_voxel, &elevation);
+        assert!(get_beam(&high_voxel, &elevation) > 1.01 * low_beam);
+
+        let constant = Elevation::Constant(200.);
+        assert_eq!(get_beam(&high_voxel, &constant), low_beam);
+    }
+}
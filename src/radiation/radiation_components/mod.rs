@@ -1,9 +1,19 @@
 pub use self::beam_component::get_beam_irradiance;
 pub use self::diffuse_component::get_diffuse_irradiance;
+use self::diffuse_component::get_horizontal_diffuse_irradiance;
+pub use self::elevation::Elevation;
+pub use self::ground_albedo::GroundAlbedo;
 pub use self::irradiance::get_irradiance;
+use self::irradiance::solar_distance_variation_correction;
+use self::reflected_component::get_reflected_irradiance;
+pub use self::weather_scale::{get_weather_scale, WeatherScale};
 pub use structs::*;
 
 mod beam_component;
 mod diffuse_component;
+mod elevation;
+mod ground_albedo;
 mod irradiance;
+mod reflected_component;
 mod structs;
+mod weather_scale;
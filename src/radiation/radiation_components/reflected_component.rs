@@ -0,0 +1,33 @@
+use nalgebra::Vector3;
+
+use super::{get_beam_irradiance, get_horizontal_diffuse_irradiance, SkyConditions};
+
+/// Irradiance reflected to the tilted surface from the horizontal ground, reflecting isotropically
+/// the global irradiance of the unshaded horizontal plane
+pub fn get_reflected_irradiance(
+    albedo: f64,
+    solar_altitude: f64,
+    normal_vector: Vector3<f64>,
+    sky_conditions: &SkyConditions,
+) -> f64 {
+    let slope = normal_vector.angle(&Vector3::z());
+    let ground_view = (1. - slope.cos()) / 2.;
+    if ground_view <= 0. {
+        return 0.;
+    }
+
+    let beam_horizontal = get_beam_irradiance(
+        sky_conditions.elevation,
+        solar_altitude,
+        solar_altitude,
+        sky_conditions.solar_distance_variation_correction,
+        sky_conditions.linke_turbidity_factor,
+    ) * sky_conditions.weather_scale.beam;
+    let diffuse_horizontal = get_horizontal_diffuse_irradiance(
+        solar_altitude,
+        sky_conditions.solar_distance_variation_correction,
+        sky_conditions.linke_turbidity_factor,
+    ) * sky_conditions.weather_scale.diffuse;
+
+    albedo * (beam_horizontal + diffuse_horizontal) * ground_view
+}
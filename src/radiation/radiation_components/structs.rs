@@ -1,3 +1,6 @@
+use nalgebra::Vector3;
+
+use super::WeatherScale;
 use crate::voxel::Voxel;
 
 pub struct VoxelIrradiance<'a> {
@@ -5,4 +8,14 @@ pub struct VoxelIrradiance<'a> {
     pub global_irradiance: f64,
     pub beam_component: f64,
     pub diffuse_component: f64,
+    pub reflected_component: f64,
+}
+
+/// Inputs of the sky models shared by the voxels at the sun position
+pub struct SkyConditions {
+    pub solar_distance_variation_correction: f64,
+    pub linke_turbidity_factor: f64,
+    pub elevation: f64,
+    pub weather_scale: WeatherScale,
+    pub sun_direction: Vector3<f64>,
 }
@@ -0,0 +1,218 @@
+use std::sync::Once;
+
+use chrono::Datelike;
+use log::warn;
+use nalgebra::Vector3;
+
+use crate::cli::input_params::diffuse_model::DiffuseModel;
+use crate::cli::input_params::weather::WeatherRecord;
+use crate::cli::InputParams;
+use crate::common::constants::SOLAR_CONSTANT;
+use crate::radiation::ray_tracing::get_sun_direction;
+use crate::radiation::sun_position::SunPosition;
+
+use super::{
+    get_beam_irradiance, get_diffuse_irradiance, solar_distance_variation_correction, SkyConditions,
+};
+
+// beam scaling is limited where the clear-sky model and the measurement disagree the most (sun at the horizon),
+// the diffuse (overcast sky may exceed the clear-sky one many times) is limited by the extraterrestrial irradiance
+const MAX_WEATHER_SCALE: f64 = 2.;
+// clear-sky irradiance [W/m2] below which the measurement is not compared
+const MIN_CLEAR_SKY_IRRADIANCE: f64 = 1.;
+
+// the clipping is reported only once, not for every sun position of every block
+static CLIPPED_SCALE_WARNING: Once = Once::new();
+
+/// Ratio of the measured and the clear-sky (ESRA) irradiance components on the horizontal plane
+#[derive(Debug, Copy, Clone)]
+pub struct WeatherScale {
+    pub beam: f64,
+    pub diffuse: f64,
+}
+
+impl Default for WeatherScale {
+    fn default() -> Self {
+        WeatherScale {
+            beam: 1.,
+            diffuse: 1.,
+        }
+    }
+}
+
+pub fn get_weather_scale(
+    input_params: &InputParams,
+    sun_position: &SunPosition,
+    record: &WeatherRecord,
+) -> WeatherScale {
+    let solar_altitude = sun_position.altitude;
+    let linke_turbidity_factor = input_params
+        .linke_turbidity_factor
+        .get_val(sun_position.time.month());
+    let correction = solar_distance_variation_correction(&sun_position.time);
+
+    let clear_beam = get_beam_irradiance(
+        input_params.get_centroid().elevation,
+        solar_altitude,
+        solar_altitude,
+        correction,
+        linke_turbidity_factor,
+    );
+    // all the diffuse models give the same horizontal irradiance
+    let clear_diffuse = get_diffuse_irradiance(
+        DiffuseModel::Esra,
+        solar_altitude,
+        solar_altitude,
+        Vector3::z(),
+        &SkyConditions {
+            solar_distance_variation_correction: correction,
+            linke_turbidity_factor,
+            elevation: input_params.get_centroid().elevation,
+            weather_scale: WeatherScale::default(),
+            sun_direction: get_sun_direction(sun_position),
+        },
+        Some(clear_beam),
+    );
+
+    let sin_altitude = solar_altitude.sin();
+    // measured diffuse cannot exceed the extraterrestrial irradiance on the horizontal plane
+    let max_diffuse_scale = if clear_diffuse > MIN_CLEAR_SKY_IRRADIANCE {
+        SOLAR_CONSTANT * correction * sin_altitude.max(0.) / clear_diffuse
+    } else {
+        f64::INFINITY
+    };
+
+    let clip_scale = |scale: f64, max_scale: f64| {
+        if scale > max_scale {
+            CLIPPED_SCALE_WARNING.call_once(|| {
+                warn!(
+                    "Weather record of {} exceeds {} times the clear-sky beam or the extraterrestrial irradiance, the scale is clipped (further records are not reported).",
+                    record.time, MAX_WEATHER_SCALE
+                )
+            });
+        }
+        scale.clamp(0., max_scale)
+    };
+
+    let get_scale = |measured: f64, clear: f64, max_scale: f64| {
+        if clear > MIN_CLEAR_SKY_IRRADIANCE {
+            clip_scale(measured / clear, max_scale)
+        } else {
+            1.
+        }
+    };
+
+    let measured = match (record.ghi, record.dhi, record.dni) {
+        (_, Some(dhi), Some(dni)) => Some((dni * sin_altitude, dhi)),
+        (Some(ghi), Some(dhi), None) => Some((ghi - dhi, dhi)),
+        (Some(ghi), None, Some(dni)) => Some((dni * sin_altitude, ghi - dni * sin_altitude)),
+        _ => None,
+    };
+
+    match (measured, record.ghi, record.clear_sky_index) {
+        (Some((beam, diffuse)), _, _) => WeatherScale {
+            beam: get_scale(beam, clear_beam, MAX_WEATHER_SCALE),
+            diffuse: get_scale(diffuse, clear_diffuse, max_diffuse_scale),
+        },
+        // without the split, both the components are scaled by the clear-sky index
+        (None, Some(ghi), _) => {
+            let clear_sky_index = get_scale(ghi, clear_beam + clear_diffuse, f64::INFINITY);
+            WeatherScale {
+                beam: clip_scale(clear_sky_index, MAX_WEATHER_SCALE),
+                diffuse: clip_scale(clear_sky_index, max_diffuse_scale),
+            }
+        }
+        (None, None, Some(clear_sky_index)) => WeatherScale {
+            beam: clip_scale(clear_sky_index, MAX_WEATHER_SCALE),
+            diffuse: clip_scale(clear_sky_index, max_diffuse_scale),
+        },
+        (None, None, None) => WeatherScale::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiation::sun_position::get_sun_positions;
+
+    fn get_scale(
+        ghi: Option<f64>,
+        dhi: Option<f64>,
+        dni: Option<f64>,
+        clear_sky_index: Option<f64>,
+    ) -> (WeatherScale, f64) {
+        let input_params =
+            InputParams::from_test_args(&["-t", "2020-06-21T10:00:00Z,2020-06-21T11:00:00Z"]);
+        let sun_position = &get_sun_positions(&input_params, None)[0];
+        let record = WeatherRecord {
+            time: sun_position.time,
+            ghi,
+            dhi,
+            dni,
+            clear_sky_index,
+            temperature: None,
+        };
+        (
+            get_weather_scale(&input_params, sun_position, &record),
+            sun_position.altitude.sin(),
+        )
+    }
+
+    #[test]
+    fn clear_sky_index_scales_both_components() {
+        let (scale, _) = get_scale(None, None, None, Some(0.5));
+        assert_eq!((scale.beam, scale.diffuse), (0.5, 0.5));
+
+        // only the beam is limited by the maximum scale
+        let (scale, _) = get_scale(None, None, None, Some(3.));
+        assert_eq!((scale.beam, scale.diffuse), (MAX_WEATHER_SCALE, 3.));
+
+        let (scale, _) = get_scale(None, None, None, None);
+        assert_eq!((scale.beam, scale.diffuse), (1., 1.));
+    }
+
+    #[test]
+    fn scales_are_proportional_to_measurement() {
+        let (scale, sin_altitude) = get_scale(None, Some(50.), Some(300.), None);
+        let (double_scale, _) = get_scale(None, Some(100.), Some(600.), None);
+        assert!((double_scale.beam - 2. * scale.beam).abs() < 1e-9);
+        assert!((double_scale.diffuse - 2. * scale.diffuse).abs() < 1e-9);
+
+        // ghi & dhi split the same way as dni & dhi
+        let ghi = 300. * sin_altitude + 50.;
+        let (ghi_scale, _) = get_scale(Some(ghi), Some(50.), None, None);
+        assert!((ghi_scale.beam - scale.beam).abs() < 1e-9);
+        assert!((ghi_scale.diffuse - scale.diffuse).abs() < 1e-9);
+
+        let (ghi_scale, _) = get_scale(Some(ghi), None, Some(300.), None);
+        assert!((ghi_scale.beam - scale.beam).abs() < 1e-9);
+        assert!((ghi_scale.diffuse - scale.diffuse).abs() < 1e-9);
+    }
+
+    #[test]
+    fn overcast_diffuse_is_limited_by_extraterrestrial_irradiance() {
+        let (scale, _) = get_scale(None, Some(200.), Some(0.), None);
+        let (double_scale, _) = get_scale(None, Some(400.), Some(0.), None);
+        assert_eq!(scale.beam, 0.);
+        assert!(double_scale.diffuse > MAX_WEATHER_SCALE);
+        assert!((double_scale.diffuse - 2. * scale.diffuse).abs() < 1e-9);
+
+        let (scale, _) = get_scale(None, Some(5000.), Some(0.), None);
+        let (double_scale, _) = get_scale(None, Some(10000.), Some(0.), None);
+        assert!(scale.diffuse > MAX_WEATHER_SCALE);
+        assert_eq!(scale.diffuse, double_scale.diffuse);
+    }
+
+    #[test]
+    fn global_irradiance_alone_scales_both_components() {
+        let (scale, _) = get_scale(Some(300.), None, None, None);
+        assert!(scale.beam > 0. && scale.beam < 1.);
+        assert_eq!(scale.beam, scale.diffuse);
+
+        let (scale, _) = get_scale(Some(0.), None, None, None);
+        assert_eq!((scale.beam, scale.diffuse), (0., 0.));
+
+        let (scale, _) = get_scale(Some(5000.), None, None, None);
+        assert_eq!(scale.beam, MAX_WEATHER_SCALE);
+    }
+}
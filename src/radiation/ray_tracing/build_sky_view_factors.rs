@@ -0,0 +1,267 @@
+use nalgebra::Vector3;
+use rayon::prelude::*;
+
+use crate::cli::input_params::horizon::Horizon;
+use crate::cli::InputParams;
+use crate::cloud_params::CloudParams;
+use crate::common::constants::PI;
+use crate::voxel::{Voxel, VoxelGrid};
+
+use super::get_lit_fraction::{get_ray_transmittance, SHADOW_BIAS};
+use super::{Transmission, VoxelGridBounds};
+
+// neighbouring voxels of the same surface would block the low rays
+const NEIGHBOUR_RADIUS: i64 = 1;
+// normals within 10° of horizontal are treated as the vertical surfaces (walls)
+const MAX_VERTICAL_NORMAL_Z: f64 = 0.17364817766693033;
+// the vertical surfaces face away from the voxels around them (roof edges, attached buildings)
+const ORIENTATION_RADIUS: i64 = 2;
+// offset [voxels] of the surrounding voxels along the normal needed to orient the surface
+const MIN_ORIENTATION_OFFSET: f64 = 0.1;
+
+/// Sky direction of the ray, directions below the horizon are obstructed
+struct SkyRay {
+    direction: Vector3<f64>,
+    is_visible: bool,
+}
+
+/// Sky view factor of the receiving voxels - visible fraction of the sky seen by the voxel surface
+/// relative to the unobstructed sky, the rays are weighted by the cosine to the voxel normal
+pub fn build_sky_view_factors(
+    voxel_grid: &mut VoxelGrid<Voxel>,
+    input_params: &InputParams,
+    cloud_params: &CloudParams,
+    block_horizon: Option<&Horizon>,
+) {
+    let sky_rays = get_sky_rays(input_params, block_horizon);
+    let bounds = VoxelGridBounds::new(voxel_grid);
+    let transmission = Transmission::new(input_params, cloud_params);
+
+    let sky_view_factors: Vec<((i64, i64, i64), f64)> = voxel_grid
+        .par_iter()
+        .filter(|(_, voxel)| voxel.role.receiver)
+        .map(|(key, voxel)| {
+            let sky_view_factor =
+                get_sky_view_factor(voxel_grid, &bounds, voxel, &sky_rays, transmission.as_ref());
+            (*key, sky_view_factor)
+        })
+        .collect();
+
+    for (key, sky_view_factor) in sky_view_factors {
+        if let Some(voxel) = voxel_grid.get_mut(&key) {
+            voxel.sky_view_factor = sky_view_factor;
+        }
+    }
+}
+
+// directions spread evenly over the upper hemisphere (Fibonacci spiral), uniform in the solid angle
+fn get_sky_rays(input_params: &InputParams, block_horizon: Option<&Horizon>) -> Vec<SkyRay> {
+    let ray_count = input_params.sky_view_rays;
+    let golden_angle = PI * (3. - 5f64.sqrt());
+    let meridian_convergence = input_params.meridian_convergence.unwrap_or(0.).to_radians();
+
+    (0..ray_count)
+        .map(|idx| {
+            let z = 1. - (idx as f64 + 0.5) / ray_count as f64;
+            let radius = (1. - z * z).sqrt();
+            let grid_azimuth = idx as f64 * golden_angle;
+            let direction =
+                Vector3::new(radius * grid_azimuth.sin(), radius * grid_azimuth.cos(), z);
+
+            let azimuth = grid_azimuth + meridian_convergence;
+            let altitude = z.asin();
+            let is_visible = input_params.horizon.is_visible(azimuth, altitude)
+                && block_horizon.is_none_or(|horizon| horizon.is_visible(azimuth, altitude));
+
+            SkyRay {
+                direction,
+                is_visible,
+            }
+        })
+        .collect()
+}
+
+// the normal of the vertical surfaces can point to both sides, the surface faces away from the voxels
+// around it - only if they lie in the surface plane (e.g. the middle of a free wall), the more open side
+// is used and the factor may be overstated; the other surfaces face up
+fn get_sky_view_factor(
+    voxel_grid: &VoxelGrid<Voxel>,
+    bounds: &VoxelGridBounds,
+    voxel: &Voxel,
+    sky_rays: &[SkyRay],
+    transmission: Option<&Transmission>,
+) -> f64 {
+    get_surface_sides(voxel_grid, voxel, voxel.normal_vector.as_na_vec())
+        .iter()
+        .filter_map(|normal| {
+            get_side_sky_view_factor(voxel_grid, bounds, voxel, normal, sky_rays, transmission)
+        })
+        .fold(None, |max: Option<f64>, value| {
+            Some(max.map_or(value, |max| max.max(value)))
+        })
+        .unwrap_or(1.)
+}
+
+fn get_surface_sides(
+    voxel_grid: &VoxelGrid<Voxel>,
+    voxel: &Voxel,
+    normal: Vector3<f64>,
+) -> Vec<Vector3<f64>> {
+    if normal.z.abs() < MAX_VERTICAL_NORMAL_Z {
+        let offset =
+            get_neighbour_offset(voxel_grid, voxel).map_or(0., |offset| offset.dot(&normal));
+        if offset > MIN_ORIENTATION_OFFSET {
+            vec![-normal]
+        } else if offset < -MIN_ORIENTATION_OFFSET {
+            vec![normal]
+        } else {
+            vec![normal, -normal]
+        }
+    } else if normal.z < 0. {
+        vec![-normal]
+    } else {
+        vec![normal]
+    }
+}
+
+// mean offset of the voxels around the voxel, None without them
+fn get_neighbour_offset(voxel_grid: &VoxelGrid<Voxel>, voxel: &Voxel) -> Option<Vector3<f64>> {
+    let range = -ORIENTATION_RADIUS..=ORIENTATION_RADIUS;
+    let offsets: Vec<Vector3<f64>> = range
+        .clone()
+        .flat_map(|dx| range.clone().map(move |dy| (dx, dy)))
+        .flat_map(|(dx, dy)| range.clone().map(move |dz| (dx, dy, dz)))
+        .filter(|&offset| offset != (0, 0, 0))
+        .filter(|(dx, dy, dz)| voxel_grid.contains_key(&(voxel.x + dx, voxel.y + dy, voxel.z + dz)))
+        .map(|(dx, dy, dz)| Vector3::new(dx as f64, dy as f64, dz as f64))
+        .collect();
+
+    (!offsets.is_empty()).then(|| offsets.iter().sum::<Vector3<f64>>() / offsets.len() as f64)
+}
+
+// rays behind the surface side don't contribute, None if no ray is in front of it
+fn get_side_sky_view_factor(
+    voxel_grid: &VoxelGrid<Voxel>,
+    bounds: &VoxelGridBounds,
+    voxel: &Voxel,
+    normal: &Vector3<f64>,
+    sky_rays: &[SkyRay],
+    transmission: Option<&Transmission>,
+) -> Option<f64> {
+    let origin =
+        Vector3::new(voxel.x as f64, voxel.y as f64, voxel.z as f64) + normal * SHADOW_BIAS;
+    let voxel_key = (voxel.x, voxel.y, voxel.z);
+
+    let mut visible_weight = 0.;
+    let mut total_weight = 0.;
+
+    for sky_ray in sky_rays {
+        let weight = normal.dot(&sky_ray.direction);
+        if weight <= 0. {
+            continue;
+        }
+        total_weight += weight;
+
+        if sky_ray.is_visible {
+            visible_weight += weight
+                * get_ray_transmittance(
+                    voxel_grid,
+                    bounds,
+                    voxel_key,
+                    NEIGHBOUR_RADIUS,
+                    &origin,
+                    &sky_ray.direction,
+                    transmission,
+                );
+        }
+    }
+
+    (total_weight > 0.).then(|| visible_weight / total_weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::NormalVector;
+
+    fn get_voxel_grid(keys: &[(i64, i64, i64)]) -> VoxelGrid<Voxel> {
+        keys.iter()
+            .map(|&(x, y, z)| ((x, y, z), Voxel::from_test_key(x, y, z)))
+            .collect()
+    }
+
+    #[test]
+    fn only_near_vertical_surfaces_have_two_sides() {
+        let voxel_grid = get_voxel_grid(&[(0, 0, 0)]);
+        let voxel = &voxel_grid[&(0, 0, 0)];
+
+        let wall = Vector3::new(0.99, 0., 0.1).normalize();
+        assert_eq!(
+            get_surface_sides(&voxel_grid, voxel, wall),
+            vec![wall, -wall]
+        );
+
+        let roof = Vector3::new(0., 0.5, 0.5).normalize();
+        assert_eq!(get_surface_sides(&voxel_grid, voxel, roof), vec![roof]);
+        assert_eq!(get_surface_sides(&voxel_grid, voxel, -roof), vec![roof]);
+    }
+
+    #[test]
+    fn vertical_surface_faces_away_from_neighbours() {
+        // wall in the y-z plane with the roof edge behind it (+x)
+        let mut keys = vec![(1, 0, 1), (2, 0, 1)];
+        for y in -2..=2 {
+            for z in -2..=1 {
+                keys.push((0, y, z));
+            }
+        }
+        let voxel_grid = get_voxel_grid(&keys);
+        let voxel = &voxel_grid[&(0, 0, 0)];
+
+        for wall in [Vector3::x(), -Vector3::x()] {
+            assert_eq!(
+                get_surface_sides(&voxel_grid, voxel, wall),
+                vec![-Vector3::x()]
+            );
+        }
+    }
+
+    #[test]
+    fn canyon_wall_sees_the_canyon_sky() {
+        // street canyon between the walls x = 0 & x = 3, a low attached roof behind the x = 3 wall
+        let mut keys = vec![];
+        for y in -10..=10 {
+            for z in 0..=12 {
+                keys.push((0, y, z));
+                keys.push((3, y, z));
+            }
+            for x in 4..=12 {
+                keys.push((x, y, 3));
+            }
+        }
+        let mut voxel_grid = get_voxel_grid(&keys);
+        let key = (3, 0, 4);
+        voxel_grid.get_mut(&key).unwrap().normal_vector = NormalVector {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        };
+
+        let input_params =
+            InputParams::from_test_args(&["--sky-view-factor", "--sky-view-rays", "256"]);
+        let sky_rays = get_sky_rays(&input_params, None);
+        let bounds = VoxelGridBounds::new(&voxel_grid);
+        let voxel = &voxel_grid[&key];
+        let get_side = |normal: Vector3<f64>| {
+            get_side_sky_view_factor(&voxel_grid, &bounds, voxel, &normal, &sky_rays, None).unwrap()
+        };
+
+        let canyon_side = get_side(-Vector3::x());
+        let open_side = get_side(Vector3::x());
+        assert!(canyon_side < 0.5 * open_side);
+        assert_eq!(
+            get_sky_view_factor(&voxel_grid, &bounds, voxel, &sky_rays, None),
+            canyon_side
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// http://www.cse.yorku.ca/~amana/research/grid.pdf
+
+use nalgebra::Vector3;
+
+use crate::voxel::{Occluder, Voxel, VoxelGrid};
+
+use super::{Transmission, VoxelGridBounds};
+
+// ray origin is moved from the voxel center towards the sun side of the surface,
+// otherwise flat surfaces would shadow themselves at low sun altitudes
+pub(super) const SHADOW_BIAS: f64 = 0.5;
+
+// rays attenuated below this transmittance are considered blocked
+const MIN_TRANSMITTANCE: f64 = 1e-4;
+
+/// Fraction of the beam reaching the voxel (averaged over the sub-rays), the sub-rays start
+/// from the cells of the n x n grid on the voxel surface (plane perpendicular to the voxel normal)
+pub fn get_lit_fraction(
+    voxel_grid: &VoxelGrid<Voxel>,
+    bounds: &VoxelGridBounds,
+    voxel: &Voxel,
+    sun_direction: &Vector3<f64>,
+    sample_offsets: &[f64],
+    transmission: Option<&Transmission>,
+) -> f64 {
+    let normal = voxel.normal_vector.as_na_vec();
+    let bias = if normal.dot(sun_direction) < 0. {
+        -normal * SHADOW_BIAS
+    } else {
+        normal * SHADOW_BIAS
+    };
+    let center = Vector3::new(voxel.x as f64, voxel.y as f64, voxel.z as f64) + bias;
+
+    let helper_axis = if normal.z.abs() < 0.9 {
+        Vector3::z()
+    } else {
+        Vector3::x()
+    };
+    let tangent_u = helper_axis.cross(&normal).normalize();
+    let tangent_v = normal.cross(&tangent_u);
+
+    let voxel_key = (voxel.x, voxel.y, voxel.z);
+    let mut lit_fraction = 0.;
+
+    for offset_u in sample_offsets {
+        for offset_v in sample_offsets {
+            let origin = center + tangent_u * *offset_u + tangent_v * *offset_v;
+            lit_fraction += get_ray_transmittance(
+                voxel_grid,
+                bounds,
+                voxel_key,
+                0,
+                &origin,
+                sun_direction,
+                transmission,
+            );
+        }
+    }
+
+    lit_fraction / sample_offsets.len().pow(2) as f64
+}
+
+/// Marches a ray towards the sun or the sky (3D DDA), voxel keys are the voxel centers.
+/// Voxels within the ignored radius (in voxels) around the voxel don't block the ray.
+/// Without transmission any voxel on the way blocks the ray.
+pub(super) fn get_ray_transmittance(
+    voxel_grid: &VoxelGrid<Voxel>,
+    bounds: &VoxelGridBounds,
+    voxel_key: (i64, i64, i64),
+    ignored_radius: i64,
+    origin: &Vector3<f64>,
+    direction: &Vector3<f64>,
+    transmission: Option<&Transmission>,
+) -> f64 {
+    let mut cell = [
+        origin.x.round() as i64,
+        origin.y.round() as i64,
+        origin.z.round() as i64,
+    ];
+    let mut step = [0i64; 3];
+    let mut t_max = [f64::INFINITY; 3];
+    let mut t_delta = [f64::INFINITY; 3];
+
+    for axis in 0..3 {
+        let axis_direction = direction[axis];
+        if axis_direction.abs() > f64::EPSILON {
+            step[axis] = axis_direction.signum() as i64;
+            let boundary = cell[axis] as f64 + 0.5 * axis_direction.signum();
+            t_max[axis] = (boundary - origin[axis]) / axis_direction;
+            t_delta[axis] = 1. / axis_direction.abs();
+        }
+    }
+
+    let mut transmittance = 1.;
+    let mut t_enter = 0.;
+
+    loop {
+        let key = (cell[0], cell[1], cell[2]);
+        let t_exit = t_max[0].min(t_max[1]).min(t_max[2]);
+
+        let is_ignored = (key.0 - voxel_key.0).abs() <= ignored_radius
+            && (key.1 - voxel_key.1).abs() <= ignored_radius
+            && (key.2 - voxel_key.2).abs() <= ignored_radius;
+        if !is_ignored {
+            if let Some(occluder) = voxel_grid.get(&key) {
+                transmittance *= match (occluder.role.occluder, transmission) {
+                    (Occluder::None, _) => 1.,
+                    (Occluder::Vegetation, Some(transmission)) => {
+                        transmission.get_transmittance(occluder, t_exit - t_enter)
+                    }
+                    _ => 0.,
+                };
+                if transmittance < MIN_TRANSMITTANCE {
+                    return 0.;
+                }
+            }
+        }
+        t_enter = t_exit;
+
+        let axis = if t_max[0] < t_max[1] {
+            if t_max[0] < t_max[2] {
+                0
+            } else {
+                2
+            }
+        } else if t_max[1] < t_max[2] {
+            1
+        } else {
+            2
+        };
+
+        cell[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+
+        // the sun & the sky rays are above the horizon, so the ray only rises
+        let is_above = cell[2] > bounds.max.2;
+        if is_above || !bounds.contains((cell[0], cell[1], cell[2].max(bounds.min.2))) {
+            return transmittance;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiation::sample_offsets::get_sample_offsets;
+    use crate::voxel::NormalVector;
+
+    fn get_voxel_grid(keys: &[(i64, i64, i64)]) -> VoxelGrid<Voxel> {
+        keys.iter()
+            .map(|&(x, y, z)| ((x, y, z), Voxel::from_test_key(x, y, z)))
+            .collect()
+    }
+
+    fn get_voxel_lit_fraction(
+        voxel_grid: &VoxelGrid<Voxel>,
+        key: (i64, i64, i64),
+        sun_direction: &Vector3<f64>,
+    ) -> f64 {
+        get_lit_fraction(
+            voxel_grid,
+            &VoxelGridBounds::new(voxel_grid),
+            &voxel_grid[&key],
+            &sun_direction.normalize(),
+            &[0.],
+            None,
+        )
+    }
+
+    #[test]
+    fn roof_shades_voxel_below() {
+        let mut keys = vec![(0, 0, 0), (10, 0, 0)];
+        for x in -3..=3 {
+            for y in -3..=3 {
+                keys.push((x, y, 5));
+            }
+        }
+        let voxel_grid = get_voxel_grid(&keys);
+
+        for sun_direction in [Vector3::z(), Vector3::new(1., 1., 4.)] {
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (0, 0, 0), &sun_direction),
+                0.
+            );
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (10, 0, 0), &sun_direction),
+                1.
+            );
+        }
+        // the roof is not shaded by itself
+        assert_eq!(
+            get_voxel_lit_fraction(&voxel_grid, (0, 0, 5), &Vector3::z()),
+            1.
+        );
+    }
+
+    #[test]
+    fn adjacent_voxel_shades_along_axis() {
+        // the biased origin (x.5) is rounded away from zero, to the neighbour for the positive
+        // keys and to the voxel itself for the negative ones
+        for x in [2, -3] {
+            let mut voxel_grid = get_voxel_grid(&[(x, 0, 0), (x + 1, 0, 0)]);
+            voxel_grid.get_mut(&(x, 0, 0)).unwrap().normal_vector = NormalVector {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            };
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (x, 0, 0), &Vector3::x()),
+                0.,
+                "{}",
+                x
+            );
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (x, 0, 0), &-Vector3::x()),
+                1.,
+                "{}",
+                x
+            );
+        }
+
+        for z in [1, -1] {
+            let voxel_grid = get_voxel_grid(&[(0, 0, z), (0, 0, z + 1), (5, 0, z)]);
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (0, 0, z), &Vector3::z()),
+                0.,
+                "{}",
+                z
+            );
+            assert_eq!(
+                get_voxel_lit_fraction(&voxel_grid, (5, 0, z), &Vector3::z()),
+                1.,
+                "{}",
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn half_covered_voxel_is_half_lit() {
+        // the roof edge ends above the voxel, the rays from the half of its surface hit the roof
+        let mut keys = vec![(0, 0, 0)];
+        for x in -6..=-2 {
+            for y in -5..=5 {
+                keys.push((x, y, 3));
+            }
+        }
+        let voxel_grid = get_voxel_grid(&keys);
+        let sun_direction = Vector3::new(-1., 0., 2.).normalize();
+
+        for samples in [2, 4] {
+            let lit_fraction = get_lit_fraction(
+                &voxel_grid,
+                &VoxelGridBounds::new(&voxel_grid),
+                &voxel_grid[&(0, 0, 0)],
+                &sun_direction,
+                &get_sample_offsets(samples),
+                None,
+            );
+            assert!((lit_fraction - 0.5).abs() < 1e-9, "{}", lit_fraction);
+        }
+    }
+
+    #[test]
+    fn vegetation_attenuates_beam() {
+        let transmission = Transmission {
+            extinction_coefficient: 0.5,
+            leaf_area_density: 0.4,
+            voxel_size: 0.5,
+        };
+        let get_transmittance = |vegetation_keys: &[(i64, i64, i64)], transmission| {
+            let mut keys = vec![(0, 0, 0)];
+            keys.extend(vegetation_keys);
+            let mut voxel_grid = get_voxel_grid(&keys);
+            for key in vegetation_keys {
+                let voxel = voxel_grid.get_mut(key).unwrap();
+                voxel.role.occluder = Occluder::Vegetation;
+                // 3 points in the voxel
+                let point = voxel.points[0].clone();
+                voxel.points.extend([point.clone(), point]);
+            }
+            get_lit_fraction(
+                &voxel_grid,
+                &VoxelGridBounds::new(&voxel_grid),
+                &voxel_grid[&(0, 0, 0)],
+                &Vector3::z(),
+                &[0.],
+                transmission,
+            )
+        };
+
+        // one voxel size path through the leaf area density of 3 points
+        let expected = (-0.5f64 * 0.4 * 3. * 0.5).exp();
+        let single = get_transmittance(&[(0, 0, 3)], Some(&transmission));
+        assert!((single - expected).abs() < 1e-12);
+        let double = get_transmittance(&[(0, 0, 3), (0, 0, 4)], Some(&transmission));
+        assert!((double - expected.powi(2)).abs() < 1e-12);
+
+        // vegetation is opaque without the extinction coefficient
+        assert_eq!(get_transmittance(&[(0, 0, 3)], None), 0.);
+    }
+
+    #[test]
+    fn opaque_voxel_blocks_beam_through_vegetation() {
+        let transmission = Transmission {
+            extinction_coefficient: 0.5,
+            leaf_area_density: 0.4,
+            voxel_size: 0.5,
+        };
+        let mut voxel_grid = get_voxel_grid(&[(0, 0, 0), (0, 0, 3), (0, 0, 6)]);
+        voxel_grid.get_mut(&(0, 0, 3)).unwrap().role.occluder = Occluder::Vegetation;
+
+        let lit_fraction = get_lit_fraction(
+            &voxel_grid,
+            &VoxelGridBounds::new(&voxel_grid),
+            &voxel_grid[&(0, 0, 0)],
+            &Vector3::z(),
+            &[0.],
+            Some(&transmission),
+        );
+        assert_eq!(lit_fraction, 0.);
+    }
+}
@@ -0,0 +1,7 @@
+mod build_sky_view_factors;
+mod get_lit_fraction;
+mod structs;
+
+pub use self::build_sky_view_factors::build_sky_view_factors;
+pub use self::get_lit_fraction::get_lit_fraction;
+pub use self::structs::*;
@@ -0,0 +1,82 @@
+use nalgebra::Vector3;
+
+use crate::{
+    cli::InputParams,
+    cloud_params::CloudParams,
+    radiation::sun_position::SunPosition,
+    voxel::{Voxel, VoxelGrid},
+};
+
+/// Extent of the voxel keys, rays leaving it cannot hit any voxel
+pub struct VoxelGridBounds {
+    pub min: (i64, i64, i64),
+    pub max: (i64, i64, i64),
+}
+
+impl VoxelGridBounds {
+    pub fn new(voxel_grid: &VoxelGrid<Voxel>) -> Self {
+        let mut bounds = VoxelGridBounds {
+            min: (i64::MAX, i64::MAX, i64::MAX),
+            max: (i64::MIN, i64::MIN, i64::MIN),
+        };
+
+        for (x, y, z) in voxel_grid.keys() {
+            bounds.min = (
+                bounds.min.0.min(*x),
+                bounds.min.1.min(*y),
+                bounds.min.2.min(*z),
+            );
+            bounds.max = (
+                bounds.max.0.max(*x),
+                bounds.max.1.max(*y),
+                bounds.max.2.max(*z),
+            );
+        }
+        bounds
+    }
+
+    pub fn contains(&self, (x, y, z): (i64, i64, i64)) -> bool {
+        (self.min.0..=self.max.0).contains(&x)
+            && (self.min.1..=self.max.1).contains(&y)
+            && (self.min.2..=self.max.2).contains(&z)
+    }
+}
+
+/// Beer-Lambert attenuation of the beam passing through the vegetation voxels
+pub struct Transmission {
+    pub extinction_coefficient: f64,
+    /// leaf area density [m2/m3] per point in the voxel
+    pub leaf_area_density: f64,
+    pub voxel_size: f64,
+}
+
+impl Transmission {
+    /// Vegetation is opaque without the extinction coefficient
+    pub fn new(input_params: &InputParams, cloud_params: &CloudParams) -> Option<Self> {
+        input_params
+            .extinction_coefficient
+            .map(|extinction_coefficient| Transmission {
+                extinction_coefficient,
+                leaf_area_density: input_params.leaf_area_density
+                    / cloud_params.average_points_in_voxel,
+                voxel_size: cloud_params.voxel_size,
+            })
+    }
+
+    /// Transmittance of the path through the voxel, path length in voxel sizes
+    pub fn get_transmittance(&self, voxel: &Voxel, path_length: f64) -> f64 {
+        let leaf_area_density = self.leaf_area_density * voxel.points.len() as f64;
+        (-self.extinction_coefficient * leaf_area_density * path_length * self.voxel_size).exp()
+    }
+}
+
+/// Unit vector pointing to the sun in the cloud (grid) coordinates
+pub fn get_sun_direction(sun_position: &SunPosition) -> Vector3<f64> {
+    let azimuth = sun_position.grid_azimuth;
+    let altitude = sun_position.altitude;
+    Vector3::new(
+        azimuth.sin() * altitude.cos(),
+        azimuth.cos() * altitude.cos(),
+        altitude.sin(),
+    )
+}
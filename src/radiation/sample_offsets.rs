@@ -0,0 +1,18 @@
+/// Offsets (in voxel sizes) of the centers of the sub-samples along one side of the voxel
+pub fn get_sample_offsets(samples: usize) -> Vec<f64> {
+    (0..samples)
+        .map(|i| (i as f64 + 0.5) / samples as f64 - 0.5)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_are_centered_in_the_voxel() {
+        assert_eq!(get_sample_offsets(1), vec![0.]);
+        assert_eq!(get_sample_offsets(2), vec![-0.25, 0.25]);
+        assert_eq!(get_sample_offsets(4), vec![-0.375, -0.125, 0.125, 0.375]);
+    }
+}
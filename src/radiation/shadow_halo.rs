@@ -0,0 +1,65 @@
+use crate::{cli::input_params::block_params::BlockHalo, cli::InputParams, common::Extent};
+
+use super::sun_position::get_sun_positions;
+
+// shadows of the sun lower than this are not covered by the default halo (they are long & faint)
+const DEFAULT_MIN_SHADOW_ALTITUDE: f64 = 5.;
+
+/// Halo around the block needed to include all the occluders casting shadow into the block
+/// (the shadow of the highest point of the cloud at the lowest sun altitude in each direction)
+pub fn get_shadow_halo(input_params: &InputParams, extent: &Extent<f64>) -> BlockHalo {
+    let (x_length, y_length, z_length) = extent.get_dimensions();
+    // occluders further than the cloud dimensions cannot exist
+    let max_length = input_params.max_shadow_halo.unwrap_or_else(|| {
+        (z_length / DEFAULT_MIN_SHADOW_ALTITUDE.to_radians().tan()).min(x_length.max(y_length))
+    });
+
+    let mut halo = BlockHalo::default();
+
+    for sun_position in get_sun_positions(input_params, None) {
+        let shadow_length = if sun_position.altitude > 0. {
+            (z_length / sun_position.altitude.tan()).min(max_length)
+        } else {
+            max_length
+        };
+
+        // occluders lie towards the sun (azimuth clockwise from north)
+        let x = sun_position.grid_azimuth.sin() * shadow_length;
+        let y = sun_position.grid_azimuth.cos() * shadow_length;
+
+        halo.left = halo.left.max(-x);
+        halo.bottom = halo.bottom.max(-y);
+        halo.right = halo.right.max(x);
+        halo.top = halo.top.max(y);
+    }
+
+    halo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_input_params(args: &[&str]) -> InputParams {
+        InputParams::from_test_args(&[&["-s", "15"], args].concat())
+    }
+
+    #[test]
+    fn halo_is_bounded_at_sunrise() {
+        let extent = Extent {
+            min: (0., 0., 0.),
+            max: (10000., 10000., 50.),
+        };
+
+        let halo = get_shadow_halo(&get_input_params(&[]), &extent);
+        let max_length = 50. / DEFAULT_MIN_SHADOW_ALTITUDE.to_radians().tan();
+        for length in [halo.left, halo.bottom, halo.right, halo.top] {
+            assert!(length <= max_length + 1e-9);
+        }
+        // the sun rises in the north-east & sets in the north-west in june
+        assert!(halo.right > 0.7 * max_length && halo.left > 0.7 * max_length);
+
+        let halo = get_shadow_halo(&get_input_params(&["--max-shadow-halo", "100"]), &extent);
+        assert!(halo.left <= 100. && halo.right <= 100.);
+    }
+}
@@ -4,29 +4,49 @@ use nalgebra::{Rotation, Rotation3};
 use spa::calc_solar_position;
 use std::f64::consts::PI;
 
-use crate::cli::{input_params::centroid::Centroid, InputParams};
+use crate::cli::{
+    input_params::{centroid::Centroid, horizon::Horizon},
+    InputParams,
+};
 
 use super::{calc_sunrise_and_set, SunriseSunset};
+use crate::radiation::radiation_components::{get_weather_scale, WeatherScale};
 
+/// Sun positions above the horizon and above the far-field horizon of the block (if computed)
 pub fn get_sun_positions(
-    InputParams {
-        time_range,
-        step_mins,
-        centroid,
-        horizon,
-        ..
-    }: &InputParams,
+    input_params: &InputParams,
+    block_horizon: Option<&Horizon>,
 ) -> Vec<SunPosition> {
-    let iter =
-        SunPositionTimeRangeIterator::new(time_range.from, time_range.to, centroid, *step_mins);
+    let InputParams {
+        step_mins, horizon, ..
+    } = input_params;
+    let time_range = input_params.get_time_range();
+    let iter = SunPositionTimeRangeIterator::new(
+        time_range.from,
+        time_range.to,
+        input_params.get_centroid(),
+        *step_mins,
+        input_params.meridian_convergence.unwrap_or(0.).to_radians(),
+    );
     let mut sun_positions: Vec<SunPosition> = vec![];
 
     for sun_pos in iter {
-        if horizon.is_visible(sun_pos.azimuth, sun_pos.altitude) {
+        if horizon.is_visible(sun_pos.azimuth, sun_pos.altitude)
+            && block_horizon
+                .is_none_or(|horizon| horizon.is_visible(sun_pos.azimuth, sun_pos.altitude))
+        {
             sun_positions.push(sun_pos)
         }
     }
 
+    if let Some(weather) = &input_params.weather {
+        for sun_position in sun_positions.iter_mut() {
+            if let Some(record) = weather.get_record(sun_position.time) {
+                sun_position.weather_scale = get_weather_scale(input_params, sun_position, record);
+            }
+        }
+    }
+
     sun_positions
 }
 
@@ -34,6 +54,7 @@ pub struct SunPositionTimeRangeIterator<'a> {
     to: DateTime<Utc>,
     centroid: &'a Centroid,
     step_mins: f64,
+    meridian_convergence: f64,
     previous_time: Option<DateTime<Utc>>,
     current_time: DateTime<Utc>,
     sunrise_sunset: Option<SunriseSunset>,
@@ -80,6 +101,15 @@ impl<'a> Iterator for SunPositionTimeRangeIterator<'a> {
                 self.current_time = sunrise;
             }
 
+            // time range starting after the sunset continues on the next day
+            if !polar_day && self.current_time >= sunset {
+                self.previous_time = Some(self.current_time);
+                self.current_time = (self.current_time + Duration::days(1))
+                    .date()
+                    .and_hms(0, 0, 0);
+                return self.next();
+            }
+
             let next_time = self.current_time + Duration::minutes(self.step_mins as i64);
 
             if next_time > sunset {
@@ -126,11 +156,13 @@ impl<'a> SunPositionTimeRangeIterator<'a> {
         to: DateTime<Utc>,
         centroid: &'a Centroid,
         step_mins: f64,
+        meridian_convergence: f64,
     ) -> Self {
         SunPositionTimeRangeIterator {
             to,
             centroid,
             step_mins,
+            meridian_convergence,
             previous_time: None,
             current_time: from,
             sunrise_sunset: None,
@@ -141,8 +173,41 @@ impl<'a> SunPositionTimeRangeIterator<'a> {
         let sol_pos = calc_solar_position(time, self.centroid.lat, self.centroid.lon).unwrap();
         let altitude = (90. - sol_pos.zenith_angle).to_radians();
         let azimuth = sol_pos.azimuth.to_radians();
+        SunPosition::new(
+            time,
+            azimuth,
+            altitude,
+            self.meridian_convergence,
+            step_coef,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct SunPosition {
+    pub rotation_x: Rotation<f64, 3>,
+    pub rotation_z: Rotation<f64, 3>,
+    pub azimuth: f64,
+    pub grid_azimuth: f64,
+    pub altitude: f64,
+    pub step_coef: f64,
+    pub time: DateTime<Utc>,
+    /// measured / clear-sky irradiance ratio (1 without the weather file)
+    pub weather_scale: WeatherScale,
+}
+
+impl SunPosition {
+    pub fn new(
+        time: DateTime<Utc>,
+        azimuth: f64,
+        altitude: f64,
+        meridian_convergence: f64,
+        step_coef: f64,
+    ) -> Self {
+        // cloud y axis points to the grid north
+        let grid_azimuth = azimuth - meridian_convergence;
         let roll = (PI / 2.) + altitude;
-        let yaw = azimuth - PI;
+        let yaw = grid_azimuth - PI;
 
         let rotation_x = Rotation3::from_euler_angles(roll, 0.0, 0.0);
         let rotation_z = Rotation3::from_euler_angles(0.0, 0.0, yaw);
@@ -150,19 +215,68 @@ impl<'a> SunPositionTimeRangeIterator<'a> {
             rotation_x,
             rotation_z,
             azimuth,
+            grid_azimuth,
             altitude,
             step_coef,
             time,
+            weather_scale: WeatherScale::default(),
         }
     }
 }
 
-#[derive(Debug)]
-pub struct SunPosition {
-    pub rotation_x: Rotation<f64, 3>,
-    pub rotation_z: Rotation<f64, 3>,
-    pub azimuth: f64,
-    pub altitude: f64,
-    pub step_coef: f64,
-    pub time: DateTime<Utc>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn time_range_starting_after_sunset_continues_next_day() {
+        let centroid = Centroid {
+            lat: 48.7,
+            lon: 19.1,
+            elevation: 200.,
+        };
+        let from = Utc.ymd(2020, 6, 21).and_hms(20, 0, 0);
+        let to = Utc.ymd(2020, 6, 22).and_hms(12, 0, 0);
+        let positions: Vec<SunPosition> =
+            SunPositionTimeRangeIterator::new(from, to, &centroid, 60., 0.).collect();
+
+        assert!(!positions.is_empty());
+        let first = positions.first().unwrap();
+        assert_eq!(first.time.ordinal(), to.ordinal());
+        assert!(positions.iter().all(|p| p.altitude >= -0.02));
+        assert!(positions
+            .iter()
+            .all(|p| p.step_coef > 0. && p.step_coef <= 1.));
+    }
+
+    #[test]
+    fn grid_azimuth_is_corrected_by_meridian_convergence() {
+        let centroid = Centroid {
+            lat: 48.7,
+            lon: 19.1,
+            elevation: 200.,
+        };
+        let from = Utc.ymd(2020, 6, 21).and_hms(10, 0, 0);
+        let to = Utc.ymd(2020, 6, 21).and_hms(11, 0, 0);
+        let convergence = 2f64.to_radians();
+
+        let true_north = SunPositionTimeRangeIterator::new(from, to, &centroid, 60., 0.)
+            .next()
+            .unwrap();
+        let grid_north = SunPositionTimeRangeIterator::new(from, to, &centroid, 60., convergence)
+            .next()
+            .unwrap();
+
+        assert_eq!(true_north.grid_azimuth, true_north.azimuth);
+        assert_eq!(grid_north.azimuth, true_north.azimuth);
+        // grid north east of the true north turns the sun azimuth to the west in the grid
+        assert!((grid_north.grid_azimuth - (true_north.azimuth - convergence)).abs() < 1e-12);
+
+        // the rotation turns the sun direction in the cloud to -z (towards the lower depth)
+        let rotation = grid_north.rotation_x * grid_north.rotation_z;
+        let sun_direction = rotation.inverse() * -nalgebra::Vector3::z();
+        let direction_azimuth = sun_direction.x.atan2(sun_direction.y);
+        assert!((direction_azimuth - grid_north.grid_azimuth).abs() < 1e-9);
+    }
 }
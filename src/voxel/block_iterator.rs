@@ -1,41 +1,69 @@
 use crate::{
-    cli::input_params::block_params::BlockParams,
+    cli::{
+        input_params::block_params::{BlockHalo, BlockParams},
+        InputParams,
+    },
     common::Extent,
-    io::Reader,
+    io::{CloudPoint, Reader},
     voxel::{GetCoords, Point, TranslatePoint, Translation, TrimDecimals},
 };
 
-use las::{Point as LasPoint, Read};
-
 pub fn get_voxel_block_iterator<'a>(
     reader: &'a Reader,
+    occluder_readers: &'a [Reader],
     extent: &'a Extent<f64>,
     block_params: BlockParams,
-) -> impl Iterator<Item = Block> + 'a {
-    let (x_length, y_length, _) = extent.get_dimensions();
-    let x_blocks = (x_length / block_params.size as f64).ceil() as usize;
-    let y_blocks = (y_length / block_params.size as f64).ceil() as usize;
+    input_params: &'a InputParams,
+) -> impl Iterator<Item = Result<Block, String>> + 'a {
+    let (x_blocks, y_blocks) = block_params.get_block_counts(extent);
+    let halo = block_params.get_halo();
 
     (0..x_blocks).flat_map(move |i| {
-        (0..y_blocks).map(move |j| {
-            let mut reader = reader.to_point_reader();
-
-            let mut block = Block::new(
-                block_params.size,
-                block_params.overlap,
-                i,
-                j,
-                x_blocks,
-                y_blocks,
-                extent,
-            );
-
-            reader
-                .points()
-                .flatten()
-                .for_each(|point| block.push_point(point));
-
-            block
+        (0..y_blocks).map(move |j| -> Result<Block, String> {
+            let mut point_source = reader
+                .to_block_point_source(i, j)
+                .map_err(|err| err.to_string())?;
+
+            let mut block = Block::new(block_params.size, halo, i, j, x_blocks, y_blocks, extent);
+            let mut outside_points = 0;
+
+            point_source
+                .by_ref()
+                .filter(|point| !input_params.is_dropped(point.attributes.classification))
+                .for_each(|point| {
+                    if !is_in_extent(&point, extent) {
+                        outside_points += 1;
+                    }
+                    block.push_point(point)
+                });
+
+            if let Some(err) = point_source.take_error() {
+                return Err(format!("Failed to read the cloud block: {}", err));
+            }
+
+            // the extent is taken from the file header if it has one, the points outside would be lost
+            if outside_points > 0 {
+                return Err(format!(
+                    "{} points of the cloud lie outside the extent of the input file header, update the header bounds",
+                    outside_points
+                ));
+            }
+
+            for occluder_reader in occluder_readers {
+                let mut point_source = occluder_reader
+                    .to_block_point_source(i, j)
+                    .map_err(|err| err.to_string())?;
+                point_source
+                    .by_ref()
+                    .filter(|point| !input_params.is_dropped(point.attributes.classification))
+                    .for_each(|point| block.push_occluder_point(point));
+
+                if let Some(err) = point_source.take_error() {
+                    return Err(format!("Failed to read the occluder cloud block: {}", err));
+                }
+            }
+
+            Ok(block)
         })
     })
 }
@@ -54,7 +82,7 @@ pub struct Block {
 impl Block {
     pub fn new(
         block_size: usize,
-        block_overlap: usize,
+        halo: BlockHalo,
         i: usize,
         j: usize,
         x_blocks: usize,
@@ -74,13 +102,14 @@ impl Block {
             z: extent.min.2.floor(),
         };
 
-        let overlap_bbox = if block_overlap > 0 {
+        let has_halo = halo.left > 0. || halo.bottom > 0. || halo.right > 0. || halo.top > 0.;
+        let overlap_bbox = if has_halo {
             let (min_x, min_y, max_x, max_y) = bbox;
             Some((
-                min_x - block_overlap as f64,
-                min_y - block_overlap as f64,
-                max_x + block_overlap as f64,
-                max_y + block_overlap as f64,
+                min_x - halo.left,
+                min_y - halo.bottom,
+                max_x + halo.right,
+                max_y + halo.top,
             ))
         } else {
             None
@@ -101,22 +130,42 @@ impl Block {
         }
     }
 
-    fn push_point(&mut self, point: LasPoint) {
+    fn push_point(&mut self, point: CloudPoint) {
         if self.is_in_overlap_block(&point) {
             let overlap = !self.is_in_block(&point);
-            let mut point = Point {
-                x: point.x,
-                y: point.y,
-                z: point.z,
-                overlap,
-            };
-            point.translate(&self.translation);
-            point.trim_decimals(3);
-
-            self.points.push(point);
+            self.push_translated_point(point, overlap);
+        }
+    }
+
+    // points of the surroundings only cast shadows like the points in the overlap
+    fn push_occluder_point(&mut self, point: CloudPoint) {
+        if self.is_in_overlap_block(&point) {
+            self.push_translated_point(point, true);
         }
     }
 
+    fn push_translated_point(&mut self, point: CloudPoint, overlap: bool) {
+        let mut point = Point {
+            x: point.x,
+            y: point.y,
+            z: point.z,
+            overlap,
+            attributes: point.attributes,
+        };
+        point.translate(&self.translation);
+        point.trim_decimals(3);
+
+        self.points.push(point);
+    }
+
+    pub fn get_bbox(&self) -> (f64, f64, f64, f64) {
+        self.bbox
+    }
+
+    pub fn get_overlap_bbox(&self) -> (f64, f64, f64, f64) {
+        self.overlap_bbox.unwrap_or(self.bbox)
+    }
+
     fn is_in_block(&self, point: &impl GetCoords) -> bool {
         let (min_x, min_y, max_x, max_y) = self.bbox;
 
@@ -140,7 +189,92 @@ impl Block {
     }
 
     fn is_in_overlap_block(&self, point: &impl GetCoords) -> bool {
-        let (min_x, min_y, max_x, max_y) = self.overlap_bbox.unwrap_or(self.bbox);
+        let (min_x, min_y, max_x, max_y) = self.get_overlap_bbox();
         point.x() >= min_x && point.y() >= min_y && point.x() <= max_x && point.y() <= max_y
     }
 }
+
+fn is_in_extent(point: &impl GetCoords, extent: &Extent<f64>) -> bool {
+    let Extent { min, max } = extent;
+    (min.0..=max.0).contains(&point.x()) && (min.1..=max.1).contains(&point.y())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::input_params::file::{File as InputFile, FileType};
+    use crate::common::TestFile;
+    use crate::io::PointAttributes;
+
+    fn get_point(x: f64, y: f64) -> CloudPoint {
+        CloudPoint {
+            x,
+            y,
+            z: 0.,
+            attributes: PointAttributes::default(),
+        }
+    }
+
+    fn get_extent(max_x: f64) -> Extent<f64> {
+        Extent {
+            min: (0., 0., 0.),
+            max: (max_x, 10., 1.),
+        }
+    }
+
+    #[test]
+    fn assigns_points_on_block_edges_once() {
+        let extent = get_extent(20.);
+        let left_block = Block::new(10, BlockHalo::default(), 0, 0, 2, 1, &extent);
+        let right_block = Block::new(10, BlockHalo::default(), 1, 0, 2, 1, &extent);
+
+        for (x, y, in_left, in_right) in [
+            (0., 0., true, false),
+            (10., 5., false, true),
+            // the last block includes the max of the extent
+            (20., 10., false, true),
+            (-0.5, 5., false, false),
+            (20.5, 5., false, false),
+        ] {
+            let point = get_point(x, y);
+            assert_eq!(left_block.is_in_block(&point), in_left, "{} {}", x, y);
+            assert_eq!(right_block.is_in_block(&point), in_right, "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn rejects_points_outside_of_extent() {
+        let file = TestFile::with_content(
+            "ply",
+            "ply\nformat ascii 1.0\nelement vertex 2\nproperty double x\nproperty double y\nproperty double z\nend_header\n1 1 0\n15 1 0\n",
+        );
+        let reader = Reader::new(&InputFile {
+            path: file.path().to_owned(),
+            file_type: FileType::Ply,
+        });
+        let input_params = InputParams::from_test_args(&[]);
+        let block_params = BlockParams {
+            size: 100,
+            overlap: 0,
+            shadow_halo: None,
+        };
+
+        let blocks: Vec<Result<Block, String>> = get_voxel_block_iterator(
+            &reader,
+            &[],
+            &get_extent(15.),
+            block_params.clone(),
+            &input_params,
+        )
+        .collect();
+        assert_eq!(blocks[0].as_ref().unwrap().points.len(), 2);
+
+        let header_extent = get_extent(10.);
+        let mut blocks =
+            get_voxel_block_iterator(&reader, &[], &header_extent, block_params, &input_params);
+        match blocks.next().unwrap() {
+            Err(err) => assert!(err.contains("outside")),
+            Ok(_) => panic!("points outside of the extent accepted"),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use rayon::prelude::*;
 use std::{collections::HashSet, error::Error, hash::BuildHasherDefault};
 use twox_hash::XxHash64;
 
-use super::{normal_from_points, Key, NormalVector, Point, Voxel, VoxelGrid};
+use super::{normal_from_points, Key, NormalQuality, NormalVector, Point, Voxel, VoxelGrid};
 
 pub fn build_normals(
     voxel_grid: &mut VoxelGrid<Voxel>,
@@ -31,15 +31,15 @@ pub fn build_normals(
 
             let failed_used_default = normal.is_none();
 
-            let normal = normal.unwrap_or_else(NormalVector::upright);
+            let (normal, normal_quality) =
+                normal.unwrap_or_else(|| (NormalVector::upright(), NormalQuality::default()));
 
-            (key, normal, failed_used_default)
+            (key, normal, normal_quality, failed_used_default)
         })
-        .collect::<Vec<(Key, NormalVector, bool)>>();
+        .collect::<Vec<(Key, NormalVector, NormalQuality, bool)>>();
 
-    normals
-        .into_iter()
-        .for_each(|(key, normal_vector, failed_used_default)| {
+    normals.into_iter().for_each(
+        |(key, normal_vector, normal_quality, failed_used_default)| {
             if failed_used_default {
                 failed_counter += 1;
             }
@@ -47,7 +47,9 @@ pub fn build_normals(
             let voxel = voxel_grid.get_mut(&key.as_tuple()).unwrap();
 
             voxel.normal_vector = normal_vector;
-        });
+            voxel.normal_quality = normal_quality;
+        },
+    );
 
     Ok(failed_counter)
 }
@@ -65,11 +67,7 @@ fn search_for_adjacent_points(
         if layer == 1 {
             if let Some(voxel) = voxel_grid.get(&key.as_tuple()) {
                 for Point { x, y, z, .. } in &voxel.points {
-                    point_set.insert((
-                        (x * 1000.) as i64,
-                        (y * 1000.) as i64,
-                        (z * 1000.) as i64,
-                    ));
+                    point_set.insert(((x * 1000.) as i64, (y * 1000.) as i64, (z * 1000.) as i64));
                 }
             }
         };
@@ -102,6 +100,7 @@ fn search_for_adjacent_points(
             y: (y as f64 / 1000.),
             z: (z as f64 / 1000.),
             overlap: false,
+            attributes: Default::default(),
         })
         .collect()
 }
@@ -0,0 +1,99 @@
+use rayon::prelude::*;
+
+use crate::cli::InputParams;
+
+use super::{Occluder, Voxel, VoxelGrid, VoxelRole};
+
+/// Returns the number of the vegetation voxels
+pub fn build_voxel_roles(voxel_grid: &mut VoxelGrid<Voxel>, input_params: &InputParams) -> usize {
+    voxel_grid.par_iter_mut().for_each(|(_, voxel)| {
+        let mut occluder = Occluder::None;
+        let mut receiver = false;
+
+        for point in &voxel.points {
+            let classification = point.attributes.classification;
+
+            if input_params.is_occluder(classification) {
+                occluder = if input_params.vegetation_classes.contains(classification) {
+                    match occluder {
+                        Occluder::None => Occluder::Vegetation,
+                        occluder => occluder,
+                    }
+                } else {
+                    Occluder::Opaque
+                };
+            }
+
+            // results of the points in the block overlap are not written
+            receiver |= !point.overlap && input_params.is_receiver(classification);
+        }
+
+        voxel.role = VoxelRole { occluder, receiver };
+    });
+
+    voxel_grid
+        .values()
+        .filter(|voxel| voxel.role.occluder == Occluder::Vegetation)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_voxel_grid(voxel_points: &[&[(u8, bool)]]) -> VoxelGrid<Voxel> {
+        voxel_points
+            .iter()
+            .enumerate()
+            .map(|(x, points)| {
+                let mut voxel = Voxel::from_test_key(x as i64, 0, 0);
+                let point = voxel.points.pop().unwrap();
+                for &(classification, overlap) in points.iter() {
+                    let mut point = point.clone();
+                    point.attributes.classification = Some(classification);
+                    point.overlap = overlap;
+                    voxel.points.push(point);
+                }
+                ((x as i64, 0, 0), voxel)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roles_follow_point_classes() {
+        let input_params = InputParams::from_test_args(&["--output-classes", "2,6"]);
+        let mut voxel_grid = get_voxel_grid(&[
+            &[(2, false)],
+            &[(5, false), (4, false)],
+            &[(5, false), (6, false)],
+            &[(6, true)],
+            &[(5, false)],
+        ]);
+
+        let vegetation_voxels = build_voxel_roles(&mut voxel_grid, &input_params);
+        assert_eq!(vegetation_voxels, 2);
+
+        let get_role = |x| {
+            let role = voxel_grid[&(x, 0, 0)].role;
+            (role.occluder, role.receiver)
+        };
+        assert_eq!(get_role(0), (Occluder::Opaque, true));
+        assert_eq!(get_role(1), (Occluder::Vegetation, false));
+        // any non-vegetation point makes the voxel opaque
+        assert_eq!(get_role(2), (Occluder::Opaque, true));
+        // points in the overlap are not written
+        assert_eq!(get_role(3), (Occluder::Opaque, false));
+        assert_eq!(get_role(4), (Occluder::Vegetation, false));
+    }
+
+    #[test]
+    fn non_occluder_classes_let_the_beam_through() {
+        let input_params = InputParams::from_test_args(&["--occluder-classes", "6"]);
+        let mut voxel_grid = get_voxel_grid(&[&[(2, false)], &[(2, false), (6, false)]]);
+
+        assert_eq!(build_voxel_roles(&mut voxel_grid, &input_params), 0);
+        assert_eq!(voxel_grid[&(0, 0, 0)].role.occluder, Occluder::None);
+        assert!(voxel_grid[&(0, 0, 0)].role.receiver);
+        assert_eq!(voxel_grid[&(1, 0, 0)].role.occluder, Occluder::Opaque);
+    }
+}
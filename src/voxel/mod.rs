@@ -1,11 +1,13 @@
 mod block_iterator;
 mod build_voxel_grid;
 mod build_normals;
+mod build_voxel_roles;
 mod normal_from_points;
 mod structs;
 
 pub use self::block_iterator::{get_voxel_block_iterator, Block};
 pub use self::build_voxel_grid::build_voxel_grid;
 pub use self::build_normals::build_normals;
+pub use self::build_voxel_roles::build_voxel_roles;
 pub use self::normal_from_points::normal_from_points;
 pub use self::structs::*;
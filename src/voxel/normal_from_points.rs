@@ -1,10 +1,10 @@
 // https://www.ilikebigbits.com/2015_03_04_plane_from_points.html
 
-use nalgebra::{vector, Vector3};
+use nalgebra::{matrix, vector, SymmetricEigen, Vector3};
 
-use super::{NormalVector, Point};
+use super::{NormalQuality, NormalVector, Point};
 
-pub fn normal_from_points(points: &[Point]) -> Option<NormalVector> {
+pub fn normal_from_points(points: &[Point]) -> Option<(NormalVector, NormalQuality)> {
     let points: &Vec<Vector3<f64>> = &points.iter().map(|point| point.as_na_vec()).collect();
 
     if points.len() < 3 {
@@ -67,7 +67,16 @@ pub fn normal_from_points(points: &[Point]) -> Option<NormalVector> {
 
     let normal_vector = normalize(dir);
 
-    Some(NormalVector::from_na_vec(&normal_vector))
+    // surface variation - smallest eigenvalue of the covariance matrix relative to their sum
+    let eigenvalues = SymmetricEigen::new(matrix![xx, xy, xz; xy, yy, yz; xz, yz, zz]).eigenvalues;
+    let curvature = eigenvalues.min().max(0.) / eigenvalues.sum();
+
+    let normal_quality = NormalQuality {
+        point_count: points.len(),
+        curvature,
+    };
+
+    Some((NormalVector::from_na_vec(&normal_vector), normal_quality))
 }
 
 fn normalize(v: Vector3<f64>) -> Vector3<f64> {
@@ -4,6 +4,8 @@ use std::hash::BuildHasherDefault;
 use std::sync::RwLock;
 use twox_hash::XxHash64;
 
+use crate::io::PointAttributes;
+
 pub trait GetCoords {
     fn x(&self) -> f64;
     fn y(&self) -> f64;
@@ -44,6 +46,10 @@ pub struct Voxel {
     pub z: i64,
     pub irradiation: RwLock<Irradiation>,
     pub normal_vector: NormalVector,
+    pub normal_quality: NormalQuality,
+    pub role: VoxelRole,
+    /// visible fraction of the sky seen by the voxel surface
+    pub sky_view_factor: f64,
     pub points: Vec<Point>,
 }
 
@@ -53,12 +59,13 @@ impl PushPoint for Voxel {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
     pub z: f64,
     pub overlap: bool,
+    pub attributes: PointAttributes,
 }
 
 impl Point {
@@ -78,6 +85,7 @@ impl IntoVoxel<Voxel> for Point {
                 global_irradiance: 0.,
                 beam_component: 0.,
                 diffuse_component: 0.,
+                reflected_component: 0.,
                 sun_hours: 0.,
             }),
             normal_vector: NormalVector {
@@ -85,11 +93,31 @@ impl IntoVoxel<Voxel> for Point {
                 y: 0.,
                 z: 0.,
             },
+            normal_quality: NormalQuality::default(),
+            role: VoxelRole::default(),
+            sky_view_factor: 1.,
             points: vec![self],
         }
     }
 }
 
+#[cfg(test)]
+impl Voxel {
+    /// Upright opaque receiving voxel of the tests with a point in its center
+    pub fn from_test_key(x: i64, y: i64, z: i64) -> Self {
+        let point = Point {
+            x: x as f64,
+            y: y as f64,
+            z: z as f64,
+            overlap: false,
+            attributes: PointAttributes::default(),
+        };
+        let mut voxel = point.to_voxel(1.);
+        voxel.normal_vector = NormalVector::upright();
+        voxel
+    }
+}
+
 impl GetCoords for Point {
     fn x(&self) -> f64 {
         self.x
@@ -180,11 +208,42 @@ impl NormalVector {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NormalQuality {
+    pub point_count: usize,
+    pub curvature: f64,
+}
+
+/// How the voxel takes part in the shadowing, based on the classification of its points
+#[derive(Copy, Clone, Debug)]
+pub struct VoxelRole {
+    pub occluder: Occluder,
+    pub receiver: bool,
+}
+
+impl Default for VoxelRole {
+    fn default() -> Self {
+        VoxelRole {
+            occluder: Occluder::Opaque,
+            receiver: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Occluder {
+    None,
+    Opaque,
+    /// occluding points are vegetation only, the voxel may be semi-transparent
+    Vegetation,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Irradiation {
     pub global_irradiance: f64,
     pub beam_component: f64,
     pub diffuse_component: f64,
+    pub reflected_component: f64,
     pub sun_hours: f64,
 }
 